@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use serde_json::json;
+
+use crate::output::{self, Format};
+use crate::walk::{total_size, Entry, Tree};
+
+/// orderings for `para list --sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortBy {
+    Name,
+    Recent,
+    Size,
+    Files,
+}
+
+pub fn last_modified(entries: &[&Entry]) -> Option<SystemTime> {
+    entries.iter().filter_map(|e| e.modified).max()
+}
+
+#[must_use]
+pub fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
+pub fn sort_modules(mut modules: Vec<PathBuf>, by: SortBy) -> Vec<PathBuf> {
+    if by == SortBy::Name {
+        modules.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        return modules;
+    }
+    let tree = Tree::scan();
+    let by_module = tree.by_module();
+    let entries = |module: &Path| by_module.get(module).cloned().unwrap_or_default();
+    // largest/newest first
+    match by {
+        SortBy::Recent => modules.sort_by_cached_key(|m|
+            std::cmp::Reverse(last_modified(&entries(m)))
+        ),
+        SortBy::Size => modules.sort_by_cached_key(|m|
+            std::cmp::Reverse(total_size(entries(m)))
+        ),
+        SortBy::Files => modules.sort_by_cached_key(|m|
+            std::cmp::Reverse(entries(m).len())
+        ),
+        SortBy::Name => (),
+    }
+    modules
+}
+
+/// modules changed in the last `days` days, most recent first, with the
+/// time of their latest change and how many files changed
+pub fn recent_modules(tree: &Tree, days: u64) -> Vec<(PathBuf, SystemTime, usize)> {
+    // too far back to represent means no cutoff at all
+    let cutoff = days.checked_mul(24 * 60 * 60)
+        .and_then(|secs| SystemTime::now().checked_sub(Duration::from_secs(secs)));
+    let recent = |t: SystemTime| cutoff.is_none_or(|cutoff| t >= cutoff);
+    let mut recent: Vec<(PathBuf, SystemTime, usize)> = tree.by_module()
+        .into_iter()
+        .filter_map(|(module, entries)| {
+            let latest = last_modified(&entries)?;
+            let changed = entries.iter()
+                .filter(|e| e.is_file() && e.modified.is_some_and(recent))
+                .count();
+            recent(latest).then(|| (module.to_path_buf(), latest, changed))
+        })
+        .collect();
+    recent.sort_by_key(|(_, latest, _)| std::cmp::Reverse(*latest));
+    recent
+}
+
+pub fn print_recent(recent: Vec<(PathBuf, SystemTime, usize)>, format: Format) {
+    if format != Format::Text {
+        let rows = recent.into_iter()
+            .map(|(module, latest, changed)| vec![
+                json!(DateTime::<Local>::from(latest).to_rfc3339()),
+                json!(changed),
+                json!(module.display().to_string()),
+            ])
+            .collect();
+        output::print_records(format, &["modified", "changed_files", "path"], rows);
+        return;
+    }
+    for (module, latest, changed) in recent {
+        println!(
+            "{} {:>5} {}",
+            format_time(latest).yellow(),
+            changed.to_string().green(),
+            module.display(),
+        );
+    }
+}
@@ -1,10 +1,18 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use chrono::NaiveDate;
 use colored::Colorize;
 use regex::Regex;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 
-use crate::{get_home_path, get_module_paths, get_root_paths, print_count, search, visit_all};
+use crate::{config, get_home_path, get_root_paths, format_bytes, print_counts, print_size, output, project, read_yaml, search};
+use crate::output::Format;
+use serde_json::json;
+use crate::walk::{total_size, Entry, Tree, IGNORE_FILE};
+use crate::git::{self, Repo};
+use crate::{index, links};
+use crate::project::Status;
 
 #[derive(Debug)]
 enum Violation {
@@ -18,11 +26,38 @@ enum Violation {
     DisallowedFile(PathBuf),
     EmptyModule(PathBuf),
     DuplicateModules(PathBuf, PathBuf),
+    DuplicateFiles {
+        files: Vec<PathBuf>,
+        size: u64,
+    },
     TooManyFiles {
         module: PathBuf,
         filecount: u64,
     },
+    TooLarge {
+        module: PathBuf,
+        bytes: u64,
+    },
     NoTags(PathBuf),
+    Overdue {
+        module: PathBuf,
+        due: NaiveDate,
+    },
+    DoneNotArchived(PathBuf),
+    NoOutcome(PathBuf),
+    DanglingSymlink(PathBuf),
+    ExternalSymlink {
+        link: PathBuf,
+        target: PathBuf,
+    },
+    MissingClone {
+        module: PathBuf,
+        repo: Repo,
+    },
+    BrokenLink {
+        file: PathBuf,
+        target: String,
+    },
 }
 
 enum Fix {
@@ -33,7 +68,13 @@ enum Fix {
         module: PathBuf,
     },
     Delete(PathBuf),
+    KeepFirst(Vec<PathBuf>),
     EditFile(PathBuf),
+    Archive(PathBuf),
+    Clone {
+        module: PathBuf,
+        repo: Repo,
+    },
     None,
 }
 
@@ -44,8 +85,16 @@ impl Violation {
             Violation::ModDirClutter(p) => Fix::MoveFile(p),
             Violation::ModDirName(p) => Fix::ModName(p),
             Violation::ModRequiredFileMissing { file, module } => Fix::CreateFile { file, module },
-            Violation::DisallowedFile(p) | Violation::EmptyModule(p) => Fix::Delete(p),
+            Violation::DisallowedFile(p) |
+            Violation::EmptyModule(p) |
+            Violation::DanglingSymlink(p) => Fix::Delete(p),
+            Violation::MissingClone { module, repo } => Fix::Clone { module, repo },
+            Violation::BrokenLink { file, .. } => Fix::EditFile(file),
             Violation::NoTags(p) => Fix::EditFile(p),
+            Violation::Overdue { module, .. } => Fix::EditFile(module.join("para.yaml")),
+            Violation::DoneNotArchived(p) => Fix::Archive(p),
+            Violation::NoOutcome(p) => Fix::EditFile(p.join("README.md")),
+            Violation::DuplicateFiles { files, .. } => Fix::KeepFirst(files),
             _ => Fix::None,
         }
     }
@@ -88,8 +137,35 @@ impl std::fmt::Display for Fix {
                 }, p.display())?;
             },
             Fix::EditFile(p) => {
-                writeln!(f, "vim {}", p.display())?;
-            }
+                writeln!(
+                    f,
+                    "{} {}",
+                    config::get_editor().unwrap_or("vim".to_string()),
+                    p.display(),
+                )?;
+            },
+            Fix::KeepFirst(files) => {
+                for p in &files[1..] {
+                    writeln!(f, "rm \"{}\"", p.display())?;
+                }
+            },
+            Fix::Archive(p) => {
+                writeln!(f, "para mv \"{}\" archive", p.file_name().unwrap().to_str().unwrap())?;
+            },
+            Fix::Clone { module, repo } => {
+                writeln!(f, "git clone {}\"{}\" \"{}\"",
+                    match &repo.branch {
+                        Some(branch) => format!("--branch \"{}\" ", branch),
+                        None => String::new(),
+                    },
+                    repo.url,
+                    repo.clone_path().display(),
+                )?;
+                let link = module.join(repo.dir_name());
+                if !link.is_symlink() {
+                    writeln!(f, "ln -s \"{}\" \"{}\"", repo.clone_path().display(), link.display())?;
+                }
+            },
             Fix::None => (),
         };
         Ok(())
@@ -97,9 +173,10 @@ impl std::fmt::Display for Fix {
 }
 
 pub fn propose_fixes(level: u32) {
-    let violations = get_violations();
+    let policies = Policies::load();
+    let (violations, _) = remove_baselined(get_violations(&policies, &Tree::scan()));
     for v in violations {
-        if v.level() <= level {
+        if v.level(&policies) <= level {
             print!("{}", v.fix());
         }
     }
@@ -137,6 +214,25 @@ impl std::fmt::Display for Violation {
             Violation::DuplicateModules(a,b) => {
                 format!("{}: {} {}", "duplicate module".red(), a.display(), b.display())
             },
+            Violation::DuplicateFiles { files, size } => {
+                format!(
+                    "{}: {} wasted {}",
+                    "duplicate files".red(),
+                    format_bytes(size * (files.len() as u64 - 1)).yellow(),
+                    files.iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                )
+            },
+            Violation::TooLarge { module, bytes } => {
+                format!(
+                    "{}: {} {}",
+                    "too large".red(),
+                    format_bytes(*bytes).yellow(),
+                    module.display()
+                )
+            },
             Violation::TooManyFiles { module, filecount } => {
                 format!(
                     "{}: {} {}",
@@ -148,13 +244,153 @@ impl std::fmt::Display for Violation {
             Violation::NoTags(yamlfile) => {
                 format!("{}: {}", "no tags".red(), yamlfile.display())
             },
+            Violation::Overdue { module, due } => {
+                format!(
+                    "{}: {} {}",
+                    "overdue project".red(),
+                    due.to_string().yellow(),
+                    module.display(),
+                )
+            },
+            Violation::DoneNotArchived(pathbuf) => {
+                format!("{}: {}", "done but not archived".red(), pathbuf.display())
+            },
+            Violation::NoOutcome(pathbuf) => {
+                format!("{}: {}", "project without goal".red(), pathbuf.display())
+            },
+            Violation::DanglingSymlink(pathbuf) => {
+                format!("{}: {}", "dangling symlink".red(), pathbuf.display())
+            },
+            Violation::ExternalSymlink { link, target } => {
+                format!(
+                    "{}: {} -> {}",
+                    "external symlink".red(),
+                    link.display(),
+                    target.display().to_string().yellow(),
+                )
+            },
+            Violation::BrokenLink { file, target } => {
+                format!(
+                    "{}: [[{}]] {}",
+                    "broken link".red(),
+                    target.yellow(),
+                    file.display(),
+                )
+            },
+            Violation::MissingClone { module, repo } => {
+                format!(
+                    "{}: {} {}",
+                    "missing clone".red(),
+                    repo.url.yellow(),
+                    module.display(),
+                )
+            },
         })?;
         Ok(())
     }
 }
 
 impl Violation {
-    fn level(&self) -> u32 {
+    /// snake_case rule name, as used in config.yaml
+    fn kind(&self) -> &'static str {
+        match self {
+            Violation::RootDirClutter(_) => "root_dir_clutter",
+            Violation::ModDirClutter(_) => "mod_dir_clutter",
+            Violation::ModDirName(_) => "mod_dir_name",
+            Violation::ModRequiredFileMissing { .. } => "missing_file",
+            Violation::DisallowedFile(_) => "disallowed_file",
+            Violation::EmptyModule(_) => "empty_module",
+            Violation::DuplicateModules(..) => "duplicate_modules",
+            Violation::DuplicateFiles { .. } => "duplicate_files",
+            Violation::TooManyFiles { .. } => "too_many_files",
+            Violation::TooLarge { .. } => "too_large",
+            Violation::NoTags(_) => "no_tags",
+            Violation::Overdue { .. } => "overdue",
+            Violation::DoneNotArchived(_) => "done_not_archived",
+            Violation::NoOutcome(_) => "no_outcome",
+            Violation::DanglingSymlink(_) => "dangling_symlink",
+            Violation::ExternalSymlink { .. } => "external_symlink",
+            Violation::MissingClone { .. } => "missing_clone",
+            Violation::BrokenLink { .. } => "broken_link",
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Violation::RootDirClutter(p) |
+            Violation::ModDirClutter(p) |
+            Violation::ModDirName(p) |
+            Violation::DisallowedFile(p) |
+            Violation::EmptyModule(p) |
+            Violation::DuplicateModules(p, _) |
+            Violation::NoTags(p) |
+            Violation::DoneNotArchived(p) |
+            Violation::NoOutcome(p) |
+            Violation::DanglingSymlink(p) => p,
+            Violation::ExternalSymlink { link, .. } => link,
+            Violation::BrokenLink { file, .. } => file,
+            Violation::DuplicateFiles { files, .. } => &files[0],
+            Violation::ModRequiredFileMissing { module, .. } |
+            Violation::TooManyFiles { module, .. } |
+            Violation::TooLarge { module, .. } |
+            Violation::Overdue { module, .. } |
+            Violation::MissingClone { module, .. } => module,
+        }
+    }
+
+    /// name of the root this violation sits in, if any
+    fn root(&self) -> Option<String> {
+        root_of(self.path())
+    }
+
+    /// module this violation sits in, if any
+    fn module(&self) -> Option<PathBuf> {
+        let home = get_home_path();
+        let relative = self.path().strip_prefix(&home).ok()?;
+        let mut components = relative.components();
+        let module = home
+            .join(components.next()?)
+            .join(components.next()?);
+        module.is_dir().then_some(module)
+    }
+
+    /// identifies a violation across audits, independent of PARA_HOME
+    fn id(&self) -> String {
+        let home = get_home_path();
+        let relative = |p: &Path| p.strip_prefix(&home)
+            .unwrap_or(p)
+            .display()
+            .to_string();
+        match self {
+            Violation::ModRequiredFileMissing { file, module } => relative(&module.join(file)),
+            Violation::DuplicateModules(a, b) => format!("{} {}", relative(a), relative(b)),
+            Violation::DuplicateFiles { files, .. } => files.iter()
+                .map(|p| relative(p))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Violation::MissingClone { module, repo } => relative(&module.join(repo.dir_name())),
+            Violation::BrokenLink { file, target } => format!("{} {}", relative(file), target),
+            v => relative(v.path()),
+        }
+    }
+
+    /// whether the module's para.yaml silences this rule with `audit: ignore: [...]`
+    fn is_ignored(&self) -> bool {
+        let Some(yaml) = self.module().and_then(|m| read_yaml(&m)) else {
+            return false;
+        };
+        yaml["audit"]["ignore"].as_sequence().is_some_and(|rules|
+            rules.iter().any(|r| r.as_str() == Some(self.kind()))
+        )
+    }
+
+    fn level(&self, policies: &Policies) -> u32 {
+        policies.get(self.kind(), self.root().as_deref())
+            .level
+            .unwrap_or(self.default_level())
+    }
+
+    fn default_level(&self) -> u32 {
         match self {
             Violation::RootDirClutter(_) => 1,
             Violation::ModDirClutter(_) => 1,
@@ -163,37 +399,135 @@ impl Violation {
             Violation::DisallowedFile(_) => 2,
             Violation::EmptyModule(_) => 1,
             Violation::DuplicateModules(..) => 1,
+            Violation::DuplicateFiles { .. } => 3,
             Violation::TooManyFiles{..} => 3,
+            Violation::TooLarge { .. } => 3,
             Violation::NoTags(..) => 4,
+            Violation::Overdue { .. } => 2,
+            Violation::DoneNotArchived(_) => 2,
+            Violation::NoOutcome(_) => 4,
+            Violation::DanglingSymlink(_) => 2,
+            Violation::ExternalSymlink { .. } => 3,
+            Violation::MissingClone { .. } => 3,
+            Violation::BrokenLink { .. } => 3,
+        }
+    }
+}
+
+fn root_of(path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(get_home_path()).ok()?;
+    let root = relative.components().next()?.as_os_str().to_str()?;
+    get_root_paths().iter()
+        .any(|r| r.file_name().unwrap() == root)
+        .then(|| root.to_string())
+}
+
+/// rules that are only checked when switched on in config.yaml
+const OPT_IN_RULES: [&str; 2] = ["no_outcome", "duplicate_files"];
+
+const DEFAULT_MAX_FILES: u64 = 1000;
+
+const DEFAULT_MAX_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+/// smallest file considered by `duplicate_files`
+const DEFAULT_MIN_DUPLICATE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+struct Policy {
+    enabled: bool,
+    level: Option<u32>,
+    threshold: Option<u64>,
+}
+
+impl Policy {
+    /// apply a rule entry from config.yaml, either a bool or a mapping of
+    /// `enabled`, `level` and `threshold`
+    fn update(&mut self, value: &serde_yaml::Value) {
+        if let Some(enabled) = value.as_bool() {
+            self.enabled = enabled;
         }
+        if let Some(enabled) = value["enabled"].as_bool() {
+            self.enabled = enabled;
+        }
+        if let Some(level) = value["level"].as_u64() {
+            self.level = Some(level as u32);
+        }
+        if let Some(threshold) = value["threshold"].as_u64() {
+            self.threshold = Some(threshold);
+        }
+    }
+}
+
+/// audit rule settings from config.yaml, with per-root overrides:
+/// ```yaml
+/// audit:
+///   rules:
+///     too_many_files: { threshold: 2000 }
+///   roots:
+///     archive:
+///       no_tags: false
+/// ```
+struct Policies {
+    audit: serde_yaml::Value,
+}
+
+impl Policies {
+    fn load() -> Self {
+        Policies {
+            audit: config::read_config()
+                .map(|c| c["audit"].clone())
+                .unwrap_or(serde_yaml::Value::Null),
+        }
+    }
+
+    fn get(&self, rule: &str, root: Option<&str>) -> Policy {
+        let mut policy = Policy {
+            enabled: !OPT_IN_RULES.contains(&rule),
+            level: None,
+            threshold: None,
+        };
+        policy.update(&self.audit["rules"][rule]);
+        if let Some(root) = root {
+            policy.update(&self.audit["roots"][root][rule]);
+        }
+        policy
     }
 }
 
-fn get_violations() -> Vec<Violation> {
+fn get_violations(policies: &Policies, tree: &Tree) -> Vec<Violation> {
     let mut violations: Vec<Violation> = vec![];
 
     let home_path = get_home_path();
     let root_paths = get_root_paths();
 
     // Check home dir for extra files/directories
-    for root_entry in home_path.read_dir().expect("failed to read dir").flatten() {
-        if root_paths.contains(&root_entry.path()) {
+    for root_entry in tree.entries.iter()
+    .filter(|e| e.path.parent() == Some(&home_path)) {
+        if root_paths.contains(&root_entry.path)
+        || root_entry.file_name() == IGNORE_FILE
+        || index::INDEX_FILES.contains(&root_entry.file_name()) {
             continue;
         } else {
-            violations.push(Violation::RootDirClutter(root_entry.path()));
+            violations.push(Violation::RootDirClutter(root_entry.path.clone()));
         }
     }
 
-    // Check root dirs for extra files/directories
-    for root_path in root_paths {
-        for mod_entry in root_path.read_dir().expect("failed to read dir").flatten() {
-            if !mod_entry.path().is_dir() {
-                violations.push(Violation::ModDirClutter(mod_entry.path()));
-            }
+    // Check root dirs for extra files/directories, directories beneath a root
+    // (or links to them) being modules
+    for mod_entry in tree.entries.iter()
+    .filter(|e| e.path.parent().is_some_and(|p| root_paths.iter().any(|r| r == p))) {
+        if mod_entry.module.is_none() && mod_entry.file_name() != IGNORE_FILE {
+            violations.push(Violation::ModDirClutter(mod_entry.path.clone()));
         }
     }
-    
-    let module_paths = get_module_paths();
+
+    // modules excluded by a .paraignore are not audited, so aren't in the tree
+    let modules = tree.by_module();
+    let mut module_paths: Vec<PathBuf> = modules.keys().map(|m| m.to_path_buf()).collect();
+    module_paths.sort_by_cached_key(|m| (
+        root_paths.iter().position(|r| m.parent() == Some(r)),
+        m.clone(),
+    ));
     let re = Regex::new("[-, ,\\.,A-Z]").unwrap();
     // this is a list of all module directories:
     module_paths.iter().for_each(|mod_entry| {
@@ -208,22 +542,20 @@ fn get_violations() -> Vec<Violation> {
         "para.yaml".to_string(),
     ];
 
-    // go into each module directory and verify that the required files are there:
+    // verify that the required files are in each module directory:
     for module in &module_paths {
-        let files: Vec<String> = module.read_dir()
-        .expect("failed to read module")
-        .filter_map(|mod_element| mod_element.ok())
-        .filter_map(|entry| 
-            entry.path().file_name().and_then(|name|
-                name.to_str().map(|s| s.to_string())
-            )
-        )
+        let entries = &modules[module.as_path()];
+        // the walk doesn't follow a linked module, so its contents are unknown
+        let linked = entries.iter().any(|e| e.path == *module && e.is_symlink);
+        let files: Vec<&str> = entries.iter()
+        .filter(|e| e.path.parent() == Some(module))
+        .map(|e| e.file_name())
         .collect();
-        if files.is_empty() {
+        if files.is_empty() && !linked {
             violations.push(Violation::EmptyModule(module.clone()));
         }
-        for required_file in &required_files {
-            if !files.contains(required_file) {
+        for required_file in required_files.iter().filter(|_| !linked) {
+            if !files.contains(&required_file.as_str()) {
                 violations.push(Violation::ModRequiredFileMissing {
                     file: required_file.clone(),
                     module: module.clone(),
@@ -236,6 +568,28 @@ fn get_violations() -> Vec<Violation> {
         }
     }
 
+    // check project deadlines and status
+    if let Ok(projects) = search::list_rooted_modules("projects") {
+        let projects = projects.into_iter().filter(|m| modules.contains_key(m.as_path()));
+        let today = project::today();
+        let check_outcome = policies.get("no_outcome", Some("projects")).enabled;
+        for module in projects {
+            let status = project::get_module_status(&module);
+            if status == Some(Status::Done) {
+                violations.push(Violation::DoneNotArchived(module));
+                continue;
+            }
+            if check_outcome && !project::has_outcome(&module) {
+                violations.push(Violation::NoOutcome(module.clone()));
+            }
+            if let Some(due) = project::get_module_due(&module) {
+                if due < today {
+                    violations.push(Violation::Overdue { module, due });
+                }
+            }
+        }
+    }
+
     let disallowed_files: Vec<String> = [
         ".git",
         ".svn",
@@ -251,14 +605,11 @@ fn get_violations() -> Vec<Violation> {
     ].iter().map(|x| x.to_string()).collect();
     
     // for the next tests, we need to check every single file/directory
-    visit_all(&home_path, &mut |pathbuf| {
-        let filename = pathbuf.file_name().unwrap()
-        .to_str().unwrap()
-        .to_string();
-        if disallowed_files.contains(&filename) {
-            violations.push(Violation::DisallowedFile(pathbuf.clone()))
+    for entry in &tree.entries {
+        if disallowed_files.iter().any(|f| f == entry.file_name()) {
+            violations.push(Violation::DisallowedFile(entry.path.clone()))
         }
-    });
+    }
 
     // check for name duplicates
     for i in 0..module_paths.len() {
@@ -278,29 +629,160 @@ fn get_violations() -> Vec<Violation> {
         }
     }
 
+    // check symlinks: they must resolve, and stay within PARA_HOME or the
+    // directory git repos are cloned into
+    let allowed_targets: Vec<PathBuf> = [home_path.clone(), git::get_clone_root()]
+        .iter()
+        .filter_map(|p| fs::canonicalize(p).ok())
+        .collect();
+    for entry in tree.entries.iter().filter(|e| e.is_symlink) {
+        match fs::canonicalize(&entry.path) {
+            Err(_) => violations.push(Violation::DanglingSymlink(entry.path.clone())),
+            Ok(target) if !allowed_targets.iter().any(|p| target.starts_with(p)) => {
+                violations.push(Violation::ExternalSymlink {
+                    link: entry.path.clone(),
+                    target,
+                });
+            },
+            Ok(_) => (),
+        }
+    }
+
+    // check that every repo in para.yaml `git` has been cloned
+    for module in &module_paths {
+        for repo in git::get_module_repos(module) {
+            let linked = module.join(repo.dir_name());
+            // a real directory in the module is fine too
+            let in_module = linked.is_dir() && !linked.is_symlink();
+            if !in_module && !repo.clone_path().exists() {
+                // the link is only dangling because the clone is missing
+                violations.retain(|v| !matches!(v, Violation::DanglingSymlink(p) if *p == linked));
+                violations.push(Violation::MissingClone {
+                    module: module.clone(),
+                    repo,
+                });
+            }
+        }
+    }
+
+    // check [[module]] links in markdown point at modules that exist
+    for link in links::get_broken_links(tree) {
+        violations.push(Violation::BrokenLink {
+            file: link.file,
+            target: link.target,
+        });
+    }
+
+    // check for identical files, hashing is expensive so this is opt-in
+    let duplicate_files = policies.get("duplicate_files", None);
+    if duplicate_files.enabled {
+        let min_size = duplicate_files.threshold.unwrap_or(DEFAULT_MIN_DUPLICATE_SIZE);
+        for (size, files) in tree.duplicate_files(min_size) {
+            violations.push(Violation::DuplicateFiles { files, size });
+        }
+    }
+
     // check for too many files
     module_paths.iter()
-    .map(|p| {
-        let mut count: u64 = 0;
-        visit_all(p, &mut |_| {count += 1;});
-        (p,count)
-    })
-    .filter(|(_,x)| *x > 1000)
+    .map(|p| (p, modules.get(p.as_path()).map_or(0, |e| e.len() as u64)))
+    .filter(|(p,x)| *x > policies.get("too_many_files", root_of(p).as_deref())
+        .threshold
+        .unwrap_or(DEFAULT_MAX_FILES)
+    )
     .for_each(|(p,count)| violations.push(Violation::TooManyFiles { 
         module: p.clone(), 
         filecount:  count
     }));
+
+    // check for modules using too much space
+    module_paths.iter()
+    .map(|p| (p, modules.get(p.as_path()).map_or(0, |e| total_size(e.iter().copied()))))
+    .filter(|(p,x)| *x > policies.get("too_large", root_of(p).as_deref())
+        .threshold
+        .unwrap_or(DEFAULT_MAX_BYTES)
+    )
+    .for_each(|(p,bytes)| violations.push(Violation::TooLarge {
+        module: p.clone(),
+        bytes,
+    }));
+
+    // drop anything switched off in config.yaml, globally or for its root,
+    // or silenced in the module's para.yaml
+    violations.retain(|v|
+        policies.get(v.kind(), v.root().as_deref()).enabled && !v.is_ignored()
+    );
     violations
 }
 
-pub fn audit(level: u32) {
-    let violations = get_violations();
+/// number of violations of each rule, ignoring the baseline
+pub fn count_violations(tree: &Tree) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for v in get_violations(&Policies::load(), tree) {
+        *counts.entry(v.kind()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn get_baseline_path() -> PathBuf {
+    config::get_para_dir().join("baseline.yaml")
+}
+
+/// violation ids accepted in baseline.yaml, keyed by rule
+fn read_baseline() -> BTreeMap<String, Vec<String>> {
+    if let Ok(f) = fs::File::open(get_baseline_path()) {
+        if let Ok(baseline) = serde_yaml::from_reader(f) {
+            return baseline;
+        }
+    }
+    BTreeMap::new()
+}
+
+/// split violations into those that are new and the number already in the baseline
+fn remove_baselined(violations: Vec<Violation>) -> (Vec<Violation>, usize) {
+    let baseline = read_baseline();
+    let (known, new): (Vec<Violation>, Vec<Violation>) = violations
+        .into_iter()
+        .partition(|v| baseline
+            .get(v.kind())
+            .is_some_and(|ids| ids.contains(&v.id()))
+        );
+    (new, known.len())
+}
+
+pub fn update_baseline() -> Result<(), String> {
+    let mut baseline: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let violations = get_violations(&Policies::load(), &Tree::scan());
+    for v in &violations {
+        baseline.entry(v.kind()).or_default().push(v.id());
+    }
+    fs::create_dir_all(config::get_para_dir()).or(Err("couldn't create .para directory"))?;
+    let f = fs::File::create(get_baseline_path()).or(Err("couldn't create baseline.yaml"))?;
+    serde_yaml::to_writer(f, &baseline).or(Err("couldn't write baseline.yaml"))?;
+    eprintln!("{}", format!(
+        "wrote {} violations to {}",
+        violations.len(),
+        get_baseline_path().display(),
+    ).green().italic());
+    Ok(())
+}
+
+/// print violations at or below `level` and return how many there are
+pub fn audit(level: u32, quiet: bool) -> usize {
+    let policies = Policies::load();
+    let (violations, baselined) = remove_baselined(get_violations(&policies, &Tree::scan()));
+    let violations: Vec<Violation> = violations
+        .into_iter()
+        .filter(|v| v.level(&policies) <= level)
+        .collect();
+
+    if quiet {
+        println!("{}", violations.len());
+        return violations.len();
+    }
 
     // print results
     for v in &violations {
-        if v.level() <= level {
-            println!("{}", v);
-        }
+        println!("{}", v);
     }
 
     // print summary
@@ -308,38 +790,269 @@ pub fn audit(level: u32) {
         match violations.len() {
             x if x > 0 => format!("{} violations", x.to_string().red()),
             _ => format!("{} violations", "zero".green()),
+        } + &match baselined {
+            0 => String::new(),
+            x => format!(" ({} in baseline)", x),
         }
     );
+    violations.len()
 }
 
-pub fn stats(min_count: u32) {
-    let mut filecount: u32 = 0;
-    visit_all(&get_home_path(), &mut |_| {filecount += 1;} );
-    print_count("total files", filecount);
+/// the audit for `para report`: violations left after the baseline, grouped
+/// by level then rule, each with its proposed fix
+pub(crate) fn report_html(tree: &Tree) -> String {
+    let policies = Policies::load();
+    let (violations, baselined) = remove_baselined(get_violations(&policies, tree));
+    let mut grouped: BTreeMap<u32, BTreeMap<&str, Vec<Violation>>> = BTreeMap::new();
+    let total = violations.len();
+    for v in violations {
+        grouped.entry(v.level(&policies))
+            .or_default()
+            .entry(v.kind())
+            .or_default()
+            .push(v);
+    }
 
-    let mut ext_count: HashMap<String,u32> = HashMap::new();
-    visit_all(
-        &get_home_path(),
-        &mut |path: &PathBuf| {
-            if path.is_file() {
-                ext_count
-                .entry(
-                    match path.extension().map(|x| x.to_str().unwrap()) {
-                        Some(ext) => ext.to_string(),
-                        None => "none".to_string(),
-                    }
-                ).and_modify(|x| *x+=1)
-                .or_insert(1);
+    let mut html = format!("<p>{} violations", total);
+    if baselined > 0 {
+        html += &format!(" ({} in baseline)", baselined);
+    }
+    html += "</p>\n<table>\n<tr><th>level</th><th>rule</th><th>count</th></tr>\n";
+    for (level, rules) in &grouped {
+        for (rule, violations) in rules {
+            html += &format!(
+                "<tr><td class=\"number\">{}</td><td>{}</td><td class=\"number\">{}</td></tr>\n",
+                level, rule, violations.len(),
+            );
+        }
+    }
+    html += "</table>\n";
+    for (level, rules) in grouped {
+        html += &format!("<h3>level {}</h3>\n", level);
+        for (rule, violations) in rules {
+            html += &format!("<details>\n<summary>{} ({})</summary>\n<ul>\n", rule, violations.len());
+            for v in violations {
+                html += &format!("<li>{}", output::html_escape(&v.to_string()));
+                let fix = v.fix().to_string();
+                if !fix.is_empty() {
+                    html += &format!("<pre>{}</pre>", output::html_escape(fix.trim_end()));
+                }
+                html += "</li>\n";
             }
+            html += "</ul>\n</details>\n";
         }
-    );
+    }
+    html
+}
+
+pub fn stats(min_count: u32, format: Format) {
+    let tree = Tree::scan();
+
+    let mut ext_count: HashMap<String,u32> = HashMap::new();
+    for entry in tree.entries.iter().filter(|e| e.is_file()) {
+        ext_count
+        .entry(entry.extension())
+        .and_modify(|x| *x+=1)
+        .or_insert(1);
+    }
 
     let mut results = ext_count
         .into_iter()
         .filter(|(_,c)| c >= &min_count)
         .collect::<Vec<(String,u32)>>();
     results.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap());
-    results.into_iter().for_each(|(a,b)|
-        print_count(&a[..], b)
-    );
-}
\ No newline at end of file
+    // the total isn't an extension, so leave it out of machine readable output
+    if format == Format::Text {
+        results.insert(0, ("total files".to_string(), tree.entries.len() as u32));
+    }
+    print_counts("extension", results, format);
+}
+
+pub fn size_stats(top: usize, format: Format) {
+    let tree = Tree::scan();
+    // (section, item, bytes)
+    let mut rows: Vec<(&str, String, u64)> = vec![];
+    rows.push(("total", "total".to_string(), total_size(&tree.entries)));
+
+    // per root
+    for root in get_root_paths() {
+        rows.push((
+            "root",
+            root.file_name().unwrap().to_str().unwrap().to_string(),
+            total_size(tree.entries.iter().filter(|e| e.path.starts_with(&root))),
+        ));
+    }
+
+    // largest modules
+    let mut module_sizes: Vec<(&Path, u64)> = tree.by_module()
+        .into_iter()
+        .map(|(module, entries)| (module, total_size(entries)))
+        .collect();
+    module_sizes.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    for (module, bytes) in module_sizes.iter().take(top) {
+        rows.push(("module", module.display().to_string(), *bytes));
+    }
+
+    // largest files
+    let mut files: Vec<&Entry> = tree.entries.iter().filter(|e| e.is_file()).collect();
+    files.sort_by_key(|e| std::cmp::Reverse(e.size));
+    for file in files.iter().take(top) {
+        rows.push(("file", file.path.display().to_string(), file.size));
+    }
+
+    // by tag, a module counts towards each of its tags
+    let mut tag_sizes: HashMap<String, u64> = HashMap::new();
+    for (module, bytes) in &module_sizes {
+        for tag in search::get_module_tags(module) {
+            *tag_sizes.entry(tag).or_insert(0) += bytes;
+        }
+    }
+    let mut tag_sizes: Vec<(String, u64)> = tag_sizes.into_iter().collect();
+    tag_sizes.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    for (tag, bytes) in tag_sizes.into_iter().take(top) {
+        rows.push(("tag", tag, bytes));
+    }
+
+    if format != Format::Text {
+        let rows = rows.into_iter()
+            .map(|(section, item, bytes)| vec![json!(section), json!(item), json!(bytes)])
+            .collect();
+        output::print_records(format, &["section", "item", "bytes"], rows);
+        return;
+    }
+    let mut section = "";
+    for (s, item, bytes) in rows {
+        if s != section && !matches!(s, "total" | "root") {
+            println!("\n{}", match s {
+                "module" => "largest modules",
+                "file" => "largest files",
+                _ => "by tag",
+            }.italic());
+        }
+        section = s;
+        print_size(&item, bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    fn policies(s: &str) -> Policies {
+        Policies { audit: yaml(s) }
+    }
+
+    #[test]
+    fn policy_update_bool() {
+        let mut policy = Policy { enabled: true, level: Some(2), threshold: None };
+        policy.update(&yaml("false"));
+        assert!(!policy.enabled);
+        assert_eq!(policy.level, Some(2));
+        policy.update(&yaml("true"));
+        assert!(policy.enabled);
+    }
+
+    #[test]
+    fn policy_update_mapping() {
+        let mut policy = Policy { enabled: false, level: None, threshold: None };
+        policy.update(&yaml("{ enabled: true, level: 7, threshold: 2000 }"));
+        assert!(policy.enabled);
+        assert_eq!(policy.level, Some(7));
+        assert_eq!(policy.threshold, Some(2000));
+    }
+
+    #[test]
+    fn policy_update_partial_mapping_keeps_the_rest() {
+        let mut policy = Policy { enabled: true, level: Some(3), threshold: Some(10) };
+        policy.update(&yaml("{ threshold: 20 }"));
+        assert!(policy.enabled);
+        assert_eq!(policy.level, Some(3));
+        assert_eq!(policy.threshold, Some(20));
+        policy.update(&serde_yaml::Value::Null);
+        assert_eq!(policy.threshold, Some(20));
+    }
+
+    #[test]
+    fn policies_defaults() {
+        let policies = policies("~");
+        assert!(policies.get("no_tags", None).enabled);
+        assert!(!policies.get("no_outcome", None).enabled);
+        assert!(!policies.get("duplicate_files", Some("projects")).enabled);
+        assert_eq!(policies.get("no_tags", None).level, None);
+    }
+
+    #[test]
+    fn policies_root_overrides_global() {
+        let policies = policies("
+rules:
+  no_tags: { level: 6 }
+  too_many_files: { threshold: 2000 }
+  no_outcome: true
+roots:
+  archive:
+    no_tags: false
+    too_many_files: { threshold: 50 }
+  projects:
+    no_outcome: { enabled: false }
+");
+        let no_tags = policies.get("no_tags", Some("archive"));
+        assert!(!no_tags.enabled);
+        assert_eq!(no_tags.level, Some(6));
+        assert!(policies.get("no_tags", Some("projects")).enabled);
+        assert_eq!(policies.get("too_many_files", Some("archive")).threshold, Some(50));
+        assert_eq!(policies.get("too_many_files", Some("areas")).threshold, Some(2000));
+        assert_eq!(policies.get("too_many_files", None).threshold, Some(2000));
+        assert!(policies.get("no_outcome", Some("areas")).enabled);
+        assert!(!policies.get("no_outcome", Some("projects")).enabled);
+    }
+
+    #[test]
+    fn violation_id_is_relative_to_home() {
+        let home = crate::test_home();
+        let module = home.join("projects").join("alpha");
+        assert_eq!(Violation::NoTags(module.join("para.yaml")).id(), "projects/alpha/para.yaml");
+        assert_eq!(
+            Violation::ModRequiredFileMissing { file: "README.md".to_string(), module: module.clone() }.id(),
+            "projects/alpha/README.md",
+        );
+        assert_eq!(
+            Violation::TooManyFiles { module: module.clone(), filecount: 5 }.id(),
+            "projects/alpha",
+        );
+        // outside PARA_HOME the path is kept as is
+        assert_eq!(Violation::ExternalSymlink {
+            link: PathBuf::from("/elsewhere/link"),
+            target: PathBuf::from("/etc"),
+        }.id(), "/elsewhere/link");
+    }
+
+    #[test]
+    fn violation_id_distinguishes_compound_violations() {
+        let home = crate::test_home();
+        let a = home.join("projects").join("alpha");
+        let b = home.join("archive").join("alpha");
+        assert_eq!(Violation::DuplicateModules(a.clone(), b.clone()).id(), "projects/alpha archive/alpha");
+        assert_eq!(Violation::DuplicateFiles {
+            files: vec![a.join("x.bin"), b.join("x.bin")],
+            size: 1,
+        }.id(), "projects/alpha/x.bin archive/alpha/x.bin");
+        assert_eq!(Violation::MissingClone {
+            module: a.clone(),
+            repo: Repo { url: "https://example.com/code/tool.git".to_string(), branch: None },
+        }.id(), "projects/alpha/tool");
+        assert_eq!(Violation::MissingClone {
+            module: a.clone(),
+            repo: Repo { url: "https://example.com/code/tool.git".to_string(), branch: Some("dev".to_string()) },
+        }.id(), "projects/alpha/tool@dev");
+        let link = |target: &str| Violation::BrokenLink {
+            file: a.join("README.md"),
+            target: target.to_string(),
+        };
+        assert_eq!(link("beta").id(), "projects/alpha/README.md beta");
+        assert_ne!(link("beta").id(), link("gamma").id());
+    }
+}
@@ -1,12 +1,20 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
-use para_audit::{audit, launch, layout, search};
+use para_audit::{activity, audit, git, graph, history, index, launch, layout, links, project, report, search, tracking};
+use para_audit::activity::SortBy;
+use para_audit::graph::GraphFormat;
+use para_audit::launch::Terminal;
+use para_audit::walk::Tree;
+use para_audit::output::{self, Format};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     #[command(subcommand)]
     command: Commands,
+    /// output format for listings and statistics
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -16,6 +24,15 @@ enum Commands {
     Audit {
         /// level of verbosity to show, 0->10
         level: Option<u32>,
+        /// accept all current violations into $PARA_HOME/.para/baseline.yaml
+        #[arg(long)]
+        update_baseline: bool,
+        /// only print the number of violations
+        #[arg(short, long)]
+        quiet: bool,
+        /// exit successfully as long as there are at most this many violations
+        #[arg(long, default_value_t = 0)]
+        max_violations: usize,
     },
     /// search para modules
     #[clap(alias = "s")]
@@ -28,12 +45,83 @@ enum Commands {
     List {
         /// module type (e.g., [project], area, resource, archive, all)
         root: Option<String>,
+        /// only show modules with a `due` date, sorted by deadline
+        #[arg(long)]
+        due: bool,
+        /// order modules by name, latest change, size or file count
+        #[arg(long, value_enum)]
+        sort: Option<SortBy>,
+    },
+    /// list recently modified modules
+    Recent {
+        /// how many days back to look
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+        /// show modules opened with `para open` rather than modified
+        #[arg(long)]
+        opened: bool,
     },
     /// open a module to work on
     #[clap(alias = "o")]
     Open {
         /// module name or substring
         module: String,
+        /// attach to (or create) a tmux session for the module
+        #[arg(long, conflicts_with = "zellij")]
+        tmux: bool,
+        /// attach to (or create) a zellij session for the module
+        #[arg(long)]
+        zellij: bool,
+    },
+    /// run a named action from a module's para.yaml, or list its actions
+    Run {
+        /// module name or substring
+        module: String,
+        /// action to run, e.g. open, edit, serve
+        action: Option<String>,
+    },
+    /// show a module's [[links]] to other modules, and links back to it
+    Links {
+        /// module name or substring
+        module: String,
+    },
+    /// write an index of every module to $PARA_HOME/INDEX.md
+    #[clap(name = "index-md")]
+    IndexMd {
+        /// write INDEX.html instead
+        #[arg(long)]
+        html: bool,
+        /// write somewhere other than $PARA_HOME
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// write the audit, stats, tags and recent activity to an html file
+    Report {
+        /// html file to write
+        #[arg(long)]
+        html: PathBuf,
+        /// how many days of activity to include
+        #[arg(long, default_value_t = 7)]
+        days: u64,
+    },
+    /// print modules, their tags and the links between them as a graph
+    Graph {
+        /// only include modules in this root
+        #[arg(long)]
+        root: Option<String>,
+        /// only include modules with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// graph syntax
+        #[arg(value_enum, default_value_t = GraphFormat::Dot)]
+        syntax: GraphFormat,
+    },
+    /// print the zsh completion script
+    Completions,
+    /// report on (or update) the git repos linked from modules
+    Git {
+        #[command(subcommand)]
+        command: GitCommands,
     },
     /// move a module between roots
     #[clap(alias = "mv")]
@@ -48,6 +136,26 @@ enum Commands {
     Stats {
         /// minimum count for showing extensions
         min_count: Option<u32>,
+        /// report disk usage per root, module, file and tag instead
+        #[arg(long)]
+        size: bool,
+        /// number of modules, files and tags to show with --size
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// show how the counts recorded by `para snapshot` changed over time
+        #[arg(long)]
+        trend: bool,
+    },
+    /// record violation, module and file counts for `para stats --trend`
+    Snapshot,
+    /// report time spent in modules opened with `para open`
+    Time {
+        /// only count this week, starting monday
+        #[arg(long)]
+        week: bool,
+        /// only count modules with this tag
+        #[arg(long)]
+        tag: Option<String>,
     },
     /// create a new module, by default in the projects root
     New {
@@ -73,52 +181,107 @@ enum Commands {
     },
 }
 
+const ZSH_COMPLETION: &str = include_str!("../completions/_para");
+
+/// find a module by exact name, or failing that by search, preferring the
+/// module opened most often when the search is ambiguous
+fn resolve_module(module: &str) -> Result<PathBuf, String> {
+    if let Some(m) = search::find_module(module) {
+        return Ok(m);
+    }
+    let potential_modules = search::search_modules(module, 0.8);
+    match potential_modules.len() {
+        1 => Ok(potential_modules[0].clone()),
+        x if x > 1 => match tracking::most_frecent(&potential_modules) {
+            Some(m) => Ok(m),
+            None => {
+                para_audit::eprint_modules(potential_modules);
+                Err("ambiguous module name".to_string())
+            },
+        },
+        _ => Err("can't find a match".to_string()),
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum GitCommands {
+    /// show branch, uncommitted changes and ahead/behind counts of every repo
+    Status,
+    /// fast-forward every repo, then show its status
+    Pull,
+}
+
 fn main() -> Result<(), String> {
     let args = Args::parse();
+    output::init_colour();
     match &args.command {
-        Commands::Audit { level } => audit::audit(level.unwrap_or(10)),
+        Commands::Audit { level, update_baseline, quiet, max_violations } => {
+            if *update_baseline {
+                audit::update_baseline()?;
+            } else if audit::audit(level.unwrap_or(10), *quiet) > *max_violations {
+                std::process::exit(1);
+            }
+        },
         Commands::Search { search_string } => {
             let modules = search::search_modules(search_string, 0.8);
-            para_audit::print_modules(modules, true);
+            para_audit::print_modules(modules, args.format);
         },
-        Commands::List { root } => {
-            match root {
+        Commands::List { root, due, sort } => {
+            let modules = match root {
                 Some(root) => match &root[..] {
-                    "all" | "a" => para_audit::print_modules(
-                        para_audit::get_module_paths(),
-                        true,
-                    ),
-                    root => para_audit::print_modules(
-                        search::list_rooted_modules(root)?,
-                        true,
-                    ),
+                    "all" | "a" => para_audit::get_module_paths(),
+                    root => search::list_rooted_modules(root)?,
                 }
-                None => para_audit::print_modules(
-                    search::list_rooted_modules("projects")?,
-                    true,
-                ),
+                None => search::list_rooted_modules("projects")?,
+            };
+            let modules = match sort {
+                Some(sort) => activity::sort_modules(modules, *sort),
+                None => modules,
+            };
+            if *due {
+                para_audit::print_deadlines(project::sort_by_due(modules), args.format);
+            } else {
+                para_audit::print_modules(modules, args.format);
             }
         },
-        Commands::Open { module } => {
-            let module_to_open = match search::find_module(module) {
-                Some(m) => {
-                    m
-                },
-                None => {
-                    let potential_modules = search::search_modules(module, 0.8);
-                    match potential_modules.len() {
-                        1 => potential_modules[0].clone(),
-                        x if x > 1 => {
-                            para_audit::eprint_modules(potential_modules);
-                            return Err("ambiguous module name".to_string());
-                        },
-                        _ => {
-                            return Err("can't find a match".to_string());
-                        }
-                    }
-                }
+        Commands::Recent { days, opened } => {
+            if *opened {
+                tracking::print_opens(*days, args.format);
+            } else {
+                activity::print_recent(activity::recent_modules(&Tree::scan(), *days), args.format);
+            }
+        },
+        Commands::Open { module, tmux, zellij } => {
+            let module_to_open = resolve_module(module)?;
+            let terminal = match (tmux, zellij) {
+                (true, _) => Terminal::Tmux,
+                (_, true) => Terminal::Zellij,
+                _ => Terminal::from_config(),
             };
-            launch::open(&module_to_open)?;
+            launch::open(&module_to_open, terminal)?;
+        },
+        Commands::Run { module, action } => {
+            let module = resolve_module(module)?;
+            match action {
+                Some(action) => launch::run_action(&module, action)?,
+                None => launch::list_actions(&module)
+                    .iter()
+                    .for_each(|action| println!("{}", action)),
+            }
+        },
+        Commands::Links { module } => links::print_links(&resolve_module(module)?, args.format),
+        Commands::IndexMd { html, output } => index::write_index(*html, output.clone())?,
+        Commands::Report { html, days } => report::write_report(html, *days)?,
+        Commands::Graph { root, tag, syntax } => {
+            if args.format != Format::Text {
+                return Err("graph is written as dot or mermaid, e.g. `para graph mermaid`".to_string());
+            }
+            graph::print_graph(root.as_deref(), tag.as_deref(), *syntax)?;
+        },
+        Commands::Completions => print!("{}", ZSH_COMPLETION),
+        Commands::Git { command } => match command {
+            GitCommands::Status => git::status(false, args.format),
+            GitCommands::Pull => git::status(true, args.format),
         },
         Commands::Move { module, destroot } => {
             let module = match search::find_module(module) {
@@ -141,7 +304,15 @@ fn main() -> Result<(), String> {
                 return Err("invalid destination name".to_string());
             }
         },
-        Commands::Stats { min_count } => audit::stats(min_count.unwrap_or(100)),
+        Commands::Stats { min_count, size, top, trend } => {
+            if *trend {
+                history::trend(args.format)?;
+            } else if *size {
+                audit::size_stats(*top, args.format);
+            } else {
+                audit::stats(min_count.unwrap_or(100), args.format);
+            }
+        },
         Commands::New { name, root } => {
             let mut module_path: PathBuf;
             if let Some(root) = root {
@@ -173,13 +344,11 @@ fn main() -> Result<(), String> {
             let count = count.unwrap_or(5);
             let mut tags = search::get_all_tags()?;
             tags.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap());
-            tags
-            .into_iter()
-            .filter(|(_,y)| y >= &count)
-            .for_each(|(x,y)|
-                para_audit::print_count(&x[..], y)
-            );
+            tags.retain(|(_,y)| y >= &count);
+            para_audit::print_counts("tag", tags, args.format);
         },
+        Commands::Snapshot => history::snapshot()?,
+        Commands::Time { week, tag } => tracking::time_report(*week, tag.as_deref(), args.format),
         Commands::Fix { level } => audit::propose_fixes(level.unwrap_or(10)),
     }
     Ok(())
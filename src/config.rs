@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::get_home_path;
+
+/// directory (beneath PARA_HOME) holding para's own configuration and data
+#[must_use]
+pub fn get_para_dir() -> PathBuf {
+    get_home_path().join(".para")
+}
+
+#[must_use]
+pub fn read_config() -> Option<serde_yaml::Value> {
+    // open $PARA_HOME/.para/config.yaml if it exists
+    if let Ok(f) = fs::File::open(get_para_dir().join("config.yaml")) {
+        if let Ok(config) = serde_yaml::from_reader(f) {
+            return Some(config)
+        }
+    }
+    None
+}
+
+/// editor from config.yaml `editor`, falling back to $VISUAL then $EDITOR
+#[must_use]
+pub fn get_editor() -> Option<String> {
+    if let Some(editor) = read_config()
+        .and_then(|c| c["editor"].as_str().map(|s| s.to_string())) {
+        return Some(editor);
+    }
+    env::var("VISUAL").or(env::var("EDITOR")).ok()
+        .filter(|e| !e.is_empty())
+}
+
+/// machine-local state (open history etc.), kept out of the synced PARA_HOME:
+/// $XDG_STATE_HOME/para, or ~/.local/state/para
+#[must_use]
+pub fn get_state_dir() -> PathBuf {
+    match env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").expect("HOME env var not defined"))
+            .join(".local")
+            .join("state"),
+    }.join("para")
+}
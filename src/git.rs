@@ -0,0 +1,446 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use colored::Colorize;
+use serde_json::json;
+
+use crate::output::{self, Format};
+use crate::{config, get_module_paths, read_yaml};
+
+/// a repository from para.yaml `git`, which is a url, a mapping of `url` and
+/// `branch`, or a sequence of either
+#[derive(Debug, Clone)]
+pub struct Repo {
+    pub url: String,
+    pub branch: Option<String>,
+}
+
+impl Repo {
+    fn from_yaml(value: &serde_yaml::Value) -> Option<Repo> {
+        match value.as_str() {
+            Some(url) => Some(Repo { url: url.to_string(), branch: None }),
+            None => Some(Repo {
+                url: value["url"].as_str()?.to_string(),
+                branch: value["branch"].as_str().map(|b| b.to_string()),
+            }),
+        }
+    }
+
+    /// git repo name, the last component of the url without `.git`
+    pub fn name(&self) -> &str {
+        self.url
+            .trim_end_matches('/')
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(".git")
+    }
+
+    /// directory the repo is cloned into and linked as, including the branch
+    /// so that different branches of a repo don't share a clone
+    pub fn dir_name(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("{}@{}", self.name(), branch.replace('/', "-")),
+            None => self.name().to_string(),
+        }
+    }
+
+    /// where the repo is cloned, outside of PARA_HOME
+    pub fn clone_path(&self) -> PathBuf {
+        self.clone_path_in(&get_clone_root())
+    }
+
+    fn clone_path_in(&self, clone_root: &Path) -> PathBuf {
+        clone_root.join(self.dir_name())
+    }
+}
+
+/// directory repos are cloned into: config.yaml `git: clone_root:`,
+/// ~/Downloads by default
+#[must_use]
+pub fn get_clone_root() -> PathBuf {
+    let home = PathBuf::from(env::var("HOME").expect("HOME env var not defined"));
+    match config::read_config()
+        .and_then(|c| c["git"]["clone_root"].as_str().map(|s| s.to_string())) {
+        Some(root) => match root.strip_prefix("~/") {
+            Some(relative) => home.join(relative),
+            None => PathBuf::from(root),
+        },
+        None => home.join("Downloads"),
+    }
+}
+
+pub fn get_module_repos(module: &Path) -> Vec<Repo> {
+    let Some(yaml) = read_yaml(module) else {
+        return vec![];
+    };
+    match yaml["git"].as_sequence() {
+        Some(repos) => repos.iter().filter_map(Repo::from_yaml).collect(),
+        None => Repo::from_yaml(&yaml["git"]).into_iter().collect(),
+    }
+}
+
+/// clone any of the module's repos that are missing, and link them into it
+pub fn init_repos(module: &Path) -> Result<(), String> {
+    let clone_root = get_clone_root();
+    for repo in get_module_repos(module) {
+        init_repo(&repo, module, &clone_root)?;
+    }
+    Ok(())
+}
+
+fn init_repo(repo: &Repo, module: &Path, clone_root: &Path) -> Result<(), String> {
+    if repo.name().is_empty() {
+        return Err(format!("para.yaml git url invalid - {}", repo.url));
+    }
+    let link = module.join(repo.dir_name());
+
+    // git url is defined, confirm that no dir with that name exists yet
+    if link.exists() {
+        // already exists, no problem.
+        return Ok(());
+    }
+
+    // check if repo in the clone root, if not, get it
+    let original = repo.clone_path_in(clone_root);
+    if let Some(url) = origin_url(&original).filter(|url| *url != repo.url) {
+        return Err(format!(
+            "{} is a clone of {}, not {}",
+            original.display(), url, repo.url,
+        ));
+    }
+    if !original.exists() {
+        // doesn't exist, clone it:
+        let mut clone = Command::new("git");
+        clone.arg("clone");
+        if let Some(branch) = &repo.branch {
+            clone.arg("--branch").arg(branch);
+        }
+        if let Ok(status) = clone.arg(&repo.url).arg(&original).status() {
+            if !status.success() {
+                return Err("git clone failed".to_string());
+            }
+        } else {
+            return Err(
+                "failed to start git".to_string()
+            );
+        }
+    }
+    // now there is a correctly named directory in the clone root,
+    // hopefully the git repo but if it's not then that's fine, whatever.
+
+    // a link left dangling by a deleted clone is replaced
+    if link.is_symlink() {
+        std::fs::remove_file(&link).map_err(|e| e.to_string())?;
+    }
+    // make symbolic link here linking to cloned repo
+    if let Err(e) = std::os::unix::fs::symlink(original, link) {
+        return Err(e.to_string())
+    }
+    Ok(())
+}
+
+/// url of the `origin` remote of the clone at `path`, if it is one
+fn origin_url(path: &Path) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    let output = Command::new("git")
+        .arg("-C").arg(path)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    output.status.success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// state of a working copy, from `git status --porcelain=v2 --branch`
+struct RepoStatus {
+    branch: String,
+    dirty: usize,
+    ahead: u32,
+    behind: u32,
+}
+
+fn repo_status(path: &Path) -> Result<RepoStatus, String> {
+    let output = Command::new("git")
+        .arg("-C").arg(path)
+        .args(["status", "--porcelain=v2", "--branch"])
+        .output()
+        .or(Err("failed to start git"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_status(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_status(porcelain: &str) -> RepoStatus {
+    let mut status = RepoStatus {
+        branch: "?".to_string(),
+        dirty: 0,
+        ahead: 0,
+        behind: 0,
+    };
+    for line in porcelain.lines() {
+        if let Some(branch) = line.strip_prefix("# branch.head ") {
+            status.branch = branch.to_string();
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            for count in ab.split_whitespace() {
+                if let Some(ahead) = count.strip_prefix('+') {
+                    status.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    status.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        } else if !line.starts_with('#') {
+            status.dirty += 1;
+        }
+    }
+    status
+}
+
+/// report dirty/ahead/behind for every repo linked from a module, after
+/// fetching, or pulling (fast-forward only) when `pull` is set
+pub fn status(pull: bool, format: Format) {
+    let mut rows = vec![];
+    for module in get_module_paths() {
+        for repo in get_module_repos(&module) {
+            let linked = module.join(repo.dir_name());
+            let path = match linked.exists() {
+                true => linked,
+                false => repo.clone_path(),
+            };
+            let label = format!(
+                "{}/{}",
+                module.file_name().unwrap().to_str().unwrap(),
+                repo.dir_name(),
+            );
+            if !path.exists() {
+                rows.push((label, Err("not cloned".to_string())));
+                continue;
+            }
+            if let Some(url) = origin_url(&path).filter(|url| *url != repo.url) {
+                rows.push((label, Err(format!("clone of {}, not {}", url, repo.url))));
+                continue;
+            }
+            // fetch so that ahead/behind are current
+            let (action, args) = match pull {
+                true => ("pull", ["pull", "--ff-only", "--quiet"]),
+                false => ("fetch", ["fetch", "--quiet", "--all"]),
+            };
+            let updated = Command::new("git")
+                .arg("-C").arg(&path)
+                .args(args)
+                .status();
+            if !updated.is_ok_and(|s| s.success()) {
+                eprintln!("{}", format!("couldn't {} {}", action, label).red().italic());
+            }
+            rows.push((label, repo_status(&path)));
+        }
+    }
+
+    if format != Format::Text {
+        let rows = rows.into_iter()
+            .map(|(label, status)| match status {
+                Ok(s) => vec![
+                    json!(label), json!(s.branch), json!(s.dirty),
+                    json!(s.ahead), json!(s.behind), json!(null),
+                ],
+                Err(e) => vec![
+                    json!(label), json!(null), json!(null),
+                    json!(null), json!(null), json!(e),
+                ],
+            })
+            .collect();
+        output::print_records(
+            format,
+            &["repo", "branch", "dirty", "ahead", "behind", "error"],
+            rows,
+        );
+        return;
+    }
+    for (label, status) in rows {
+        match status {
+            Ok(s) => println!(
+                "{} {} {}{}{}",
+                label.green(),
+                s.branch.italic(),
+                match s.dirty {
+                    0 => "clean".normal(),
+                    n => format!("{} changed", n).red(),
+                },
+                match s.ahead {
+                    0 => String::new(),
+                    n => format!(" {}", format!("+{} ahead", n).yellow()),
+                },
+                match s.behind {
+                    0 => String::new(),
+                    n => format!(" {}", format!("-{} behind", n).yellow()),
+                },
+            ),
+            Err(e) => println!("{} {}", label.green(), e.red()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn repo(url: &str, branch: Option<&str>) -> Repo {
+        Repo { url: url.to_string(), branch: branch.map(|b| b.to_string()) }
+    }
+
+    /// an empty scratch directory, unique to the test
+    fn scratch(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("para-git-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C").arg(dir)
+            .args(["-c", "user.name=para", "-c", "user.email=para@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// a repo with a commit on `main` and on `dev`, as a file:// url
+    fn upstream(dir: &Path) -> String {
+        let upstream = dir.join("upstream").join("tool");
+        fs::create_dir_all(&upstream).unwrap();
+        git(&upstream, &["init", "--quiet", "--initial-branch", "main"]);
+        git(&upstream, &["commit", "--quiet", "--allow-empty", "-m", "main"]);
+        git(&upstream, &["checkout", "--quiet", "-b", "dev"]);
+        git(&upstream, &["commit", "--quiet", "--allow-empty", "-m", "dev"]);
+        git(&upstream, &["checkout", "--quiet", "main"]);
+        format!("file://{}", upstream.display())
+    }
+
+    #[test]
+    fn repo_from_yaml() {
+        let parsed = |s: &str| Repo::from_yaml(&serde_yaml::from_str(s).unwrap());
+        let r = parsed("https://example.com/me/tool.git").unwrap();
+        assert_eq!((r.url.as_str(), r.branch), ("https://example.com/me/tool.git", None));
+        let r = parsed("{ url: git@example.com:me/tool.git, branch: dev }").unwrap();
+        assert_eq!((r.url.as_str(), r.branch.as_deref()), ("git@example.com:me/tool.git", Some("dev")));
+        assert!(parsed("{ branch: dev }").is_none());
+    }
+
+    #[test]
+    fn module_repos() {
+        let module = scratch("module-repos");
+        fs::write(module.join("para.yaml"), "git:\n  - file:///srv/a.git\n  - url: file:///srv/b\n    branch: dev\n").unwrap();
+        let repos = get_module_repos(&module);
+        assert_eq!(repos.iter().map(|r| r.dir_name()).collect::<Vec<String>>(), ["a", "b@dev"]);
+        fs::write(module.join("para.yaml"), "git: file:///srv/a.git\n").unwrap();
+        assert_eq!(get_module_repos(&module).len(), 1);
+        fs::write(module.join("para.yaml"), "tags: [x]\n").unwrap();
+        assert!(get_module_repos(&module).is_empty());
+        fs::remove_dir_all(module).unwrap();
+    }
+
+    #[test]
+    fn repo_name() {
+        assert_eq!(repo("https://github.com/jcranney/para-audit.git", None).name(), "para-audit");
+        assert_eq!(repo("https://example.com/me/tool/", None).name(), "tool");
+        assert_eq!(repo("git@example.com:me/thesis.git", None).name(), "thesis");
+        assert_eq!(repo("git@example.com:thesis.git", None).name(), "thesis");
+        assert_eq!(repo("file:///tmp/gitsrv/two.git", None).name(), "two");
+    }
+
+    #[test]
+    fn dir_name_includes_branch() {
+        assert_eq!(repo("file:///srv/tool.git", None).dir_name(), "tool");
+        assert_eq!(repo("file:///srv/tool.git", Some("dev")).dir_name(), "tool@dev");
+        assert_eq!(repo("file:///srv/tool.git", Some("feature/x")).dir_name(), "tool@feature-x");
+        let root = Path::new("/clones");
+        assert_ne!(
+            repo("file:///srv/tool.git", Some("main")).clone_path_in(root),
+            repo("file:///srv/tool.git", Some("dev")).clone_path_in(root),
+        );
+    }
+
+    #[test]
+    fn parse_porcelain_v2() {
+        let status = parse_status(concat!(
+            "# branch.oid 0123456789abcdef\n",
+            "# branch.head dev\n",
+            "# branch.upstream origin/dev\n",
+            "# branch.ab +2 -3\n",
+            "1 .M N... 100644 100644 100644 abc abc src/main.rs\n",
+            "? notes.txt\n",
+        ));
+        assert_eq!(status.branch, "dev");
+        assert_eq!((status.dirty, status.ahead, status.behind), (2, 2, 3));
+
+        let status = parse_status("# branch.oid (initial)\n# branch.head (detached)\n");
+        assert_eq!(status.branch, "(detached)");
+        assert_eq!((status.dirty, status.ahead, status.behind), (0, 0, 0));
+    }
+
+    #[test]
+    fn init_repo_clones_each_branch() {
+        let dir = scratch("init-branches");
+        let url = upstream(&dir);
+        let clone_root = dir.join("clones");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+
+        init_repo(&repo(&url, Some("main")), &a, &clone_root).unwrap();
+        init_repo(&repo(&url, Some("dev")), &b, &clone_root).unwrap();
+        assert_eq!(repo_status(&a.join("tool@main")).unwrap().branch, "main");
+        assert_eq!(repo_status(&b.join("tool@dev")).unwrap().branch, "dev");
+        assert!(clone_root.join("tool@main").is_dir());
+        assert!(clone_root.join("tool@dev").is_dir());
+
+        // a second module reuses the existing clone
+        let c = dir.join("c");
+        fs::create_dir_all(&c).unwrap();
+        init_repo(&repo(&url, Some("dev")), &c, &clone_root).unwrap();
+        assert_eq!(fs::read_link(c.join("tool@dev")).unwrap(), clone_root.join("tool@dev"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn init_repo_reclones_behind_a_dangling_link() {
+        let dir = scratch("init-dangling");
+        let url = upstream(&dir);
+        let clone_root = dir.join("clones");
+        let module = dir.join("module");
+        fs::create_dir_all(&module).unwrap();
+
+        init_repo(&repo(&url, None), &module, &clone_root).unwrap();
+        fs::remove_dir_all(clone_root.join("tool")).unwrap();
+        assert!(module.join("tool").is_symlink() && !module.join("tool").exists());
+
+        init_repo(&repo(&url, None), &module, &clone_root).unwrap();
+        assert_eq!(fs::read_link(module.join("tool")).unwrap(), clone_root.join("tool"));
+        assert_eq!(repo_status(&module.join("tool")).unwrap().branch, "main");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn init_repo_rejects_clone_of_another_url() {
+        let dir = scratch("init-collision");
+        let url = upstream(&dir);
+        let clone_root = dir.join("clones");
+        let module = dir.join("module");
+        fs::create_dir_all(&module).unwrap();
+
+        init_repo(&repo(&url, None), &module, &clone_root).unwrap();
+        let other = dir.join("other");
+        fs::create_dir_all(&other).unwrap();
+        let err = init_repo(&repo("file:///elsewhere/tool.git", None), &other, &clone_root).unwrap_err();
+        assert!(err.contains("is a clone of"), "{}", err);
+        assert!(!other.join("tool").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
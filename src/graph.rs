@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::links::parse_links;
+use crate::search::{find_root, get_module_tags};
+use crate::{get_module_paths, get_root_paths};
+
+/// how `para graph` is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+/// modules grouped into roots, connected to their tags and to the modules
+/// their README links to. Modules are identified as `root/name`, since a
+/// name can appear in more than one root.
+struct Graph {
+    /// root name -> module ids
+    roots: Vec<(String, Vec<String>)>,
+    /// module id -> tags
+    tags: BTreeMap<String, Vec<String>>,
+    /// (from, to) module ids
+    links: BTreeSet<(String, String)>,
+}
+
+fn name(module: &Path) -> String {
+    module.file_name().unwrap().to_str().unwrap().to_string()
+}
+
+/// `root/name`
+fn id(module: &Path) -> String {
+    format!("{}/{}", name(module.parent().unwrap()), name(module))
+}
+
+/// the module name in a `root/name` id
+fn label(id: &str) -> &str {
+    id.split_once('/').map_or(id, |(_, name)| name)
+}
+
+impl Graph {
+    /// modules in `root` (or every root) tagged with `tag` (or anything)
+    fn build(root: Option<&str>, tag: Option<&str>) -> Graph {
+        let mut modules: Vec<(PathBuf, Vec<String>)> = get_module_paths()
+            .into_iter()
+            .filter(|m| root.is_none_or(|r| m.parent().unwrap().file_name().unwrap() == r))
+            .map(|m| {
+                let tags = get_module_tags(&m);
+                (m, tags)
+            })
+            .filter(|(_, tags)| tag.is_none_or(|t| tags.iter().any(|x| x == t)))
+            .collect();
+        modules.sort_by(|(a, _), (b, _)| a.file_name().cmp(&b.file_name()));
+
+        let roots = get_root_paths().iter()
+            .map(|r| (
+                name(r),
+                modules.iter()
+                    .filter(|(m, _)| m.parent() == Some(r))
+                    .map(|(m, _)| id(m))
+                    .collect::<Vec<String>>(),
+            ))
+            .filter(|(_, modules)| !modules.is_empty())
+            .collect();
+        let tags: BTreeMap<String, Vec<String>> = modules.iter()
+            .map(|(m, tags)| (id(m), tags.clone()))
+            .collect();
+
+        // only the READMEs of the modules in the graph are read
+        let mut by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (m, _) in &modules {
+            by_name.entry(name(m)).or_default().push(id(m));
+        }
+        let mut links = BTreeSet::new();
+        for (m, _) in &modules {
+            let Ok(readme) = fs::read_to_string(m.join("README.md")) else {
+                continue;
+            };
+            let from = id(m);
+            for target in parse_links(&readme) {
+                for to in by_name.get(&target).into_iter().flatten() {
+                    if *to != from {
+                        links.insert((from.clone(), to.clone()));
+                    }
+                }
+            }
+        }
+        Graph { roots, tags, links }
+    }
+
+    fn all_tags(&self) -> BTreeSet<&String> {
+        self.tags.values().flatten().collect()
+    }
+
+    /// graphviz, one cluster per root
+    fn dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let tag_id = |t: &str| quote(&format!("tag:{}", t));
+        let mut dot = String::from("digraph para {\n  rankdir=LR;\n  node [shape=box];\n");
+        for (root, modules) in &self.roots {
+            dot += &format!("  subgraph {} {{\n    label={};\n", quote(&format!("cluster_{}", root)), quote(root));
+            for module in modules {
+                dot += &format!("    {} [label={}];\n", quote(module), quote(label(module)));
+            }
+            dot += "  }\n";
+        }
+        for tag in self.all_tags() {
+            dot += &format!("  {} [label={}, shape=ellipse];\n", tag_id(tag), quote(&format!("#{}", tag)));
+        }
+        for (module, tags) in &self.tags {
+            for tag in tags {
+                dot += &format!("  {} -> {} [arrowhead=none, color=gray];\n", quote(module), tag_id(tag));
+            }
+        }
+        for (from, to) in &self.links {
+            dot += &format!("  {} -> {};\n", quote(from), quote(to));
+        }
+        dot + "}\n"
+    }
+
+    /// mermaid flowchart, one subgraph per root
+    fn mermaid(&self) -> String {
+        let quoted = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+        // mermaid ids can't contain arbitrary characters, so number them
+        let module_ids: BTreeMap<&String, String> = self.tags.keys()
+            .enumerate()
+            .map(|(i, m)| (m, format!("m{}", i)))
+            .collect();
+        let tag_ids: BTreeMap<&String, String> = self.all_tags()
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| (t, format!("t{}", i)))
+            .collect();
+        let mut mermaid = String::from("graph LR\n");
+        for (i, (root, modules)) in self.roots.iter().enumerate() {
+            mermaid += &format!("  subgraph r{} [{}]\n", i, quoted(root));
+            for module in modules {
+                mermaid += &format!("    {}[{}]\n", module_ids[module], quoted(label(module)));
+            }
+            mermaid += "  end\n";
+        }
+        for (tag, id) in &tag_ids {
+            mermaid += &format!("  {}([{}])\n", id, quoted(&format!("#{}", tag)));
+        }
+        for (module, tags) in &self.tags {
+            for tag in tags {
+                mermaid += &format!("  {} --- {}\n", module_ids[module], tag_ids[tag]);
+            }
+        }
+        for (from, to) in &self.links {
+            mermaid += &format!("  {} --> {}\n", module_ids[from], module_ids[to]);
+        }
+        mermaid
+    }
+}
+
+/// print the module graph as graphviz dot or mermaid
+pub fn print_graph(root: Option<&str>, tag: Option<&str>, format: GraphFormat) -> Result<(), String> {
+    if let Some(root) = root {
+        find_root(root).ok_or(format!("invalid root name - {}", root))?;
+    }
+    let graph = Graph::build(root, tag);
+    match format {
+        GraphFormat::Dot => print!("{}", graph.dot()),
+        GraphFormat::Mermaid => print!("{}", graph.mermaid()),
+    }
+    Ok(())
+}
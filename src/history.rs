@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use colored::Colorize;
+use serde_json::{json, Value};
+
+use crate::output::{self, Format};
+use crate::walk::{total_size, Entry, Tree};
+use crate::{audit, config, format_bytes, get_root_paths};
+
+const SPARK: [char; 8] = ['_', '.', ',', '-', '~', '=', '*', '#'];
+
+fn get_history_path() -> PathBuf {
+    config::get_para_dir().join("history.jsonl")
+}
+
+/// append violation, module, file and size counts to history.jsonl
+pub fn snapshot() -> Result<(), String> {
+    let tree = Tree::scan();
+    let modules = tree.by_module();
+    let roots = get_root_paths();
+    let module_counts: BTreeMap<String, usize> = roots
+        .iter()
+        .map(|root| (
+            root.file_name().unwrap().to_str().unwrap().to_string(),
+            modules.keys().filter(|m| m.parent() == Some(root)).count(),
+        ))
+        .collect();
+    // only files in the roots, not stray files in PARA_HOME
+    let files: Vec<&Entry> = tree.entries.iter()
+        .filter(|e| e.is_file() && roots.iter().any(|r| e.path.starts_with(r)))
+        .collect();
+    let record = json!({
+        "time": chrono::Local::now().to_rfc3339(),
+        "violations": audit::count_violations(&tree),
+        "modules": module_counts,
+        "files": files.len(),
+        "bytes": total_size(files),
+    });
+
+    fs::create_dir_all(config::get_para_dir()).or(Err("couldn't create .para directory"))?;
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_history_path())
+        .or(Err("couldn't open history.jsonl"))?;
+    writeln!(f, "{}", record).or(Err("couldn't write to history.jsonl"))?;
+    eprintln!("{}", format!("recorded snapshot in {}", get_history_path().display()).green().italic());
+    Ok(())
+}
+
+fn read_history() -> Result<Vec<Value>, String> {
+    let history = fs::read_to_string(get_history_path())
+        .or(Err("no snapshots yet, run `para snapshot` first"))?;
+    Ok(history.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn sparkline(values: &[u64]) -> String {
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    values.iter()
+        .map(|v| match max - min {
+            0 => SPARK[0],
+            range => SPARK[((v - min) * (SPARK.len() as u64 - 1) / range) as usize],
+        })
+        .collect()
+}
+
+/// every metric found in the snapshots, with its value in each of them
+fn metrics(history: &[Value]) -> Vec<(String, Vec<u64>)> {
+    let mut names: Vec<String> = vec!["violations".to_string()];
+    for group in ["violations", "modules"] {
+        let keys: BTreeSet<&String> = history.iter()
+            .filter_map(|record| record[group].as_object())
+            .flat_map(|map| map.keys())
+            .collect();
+        names.extend(keys.into_iter().map(|key| format!("{}/{}", group, key)));
+    }
+    names.push("files".to_string());
+    names.push("bytes".to_string());
+
+    names.into_iter()
+        .map(|name| {
+            let values = history.iter()
+                .map(|record| match name.split_once('/') {
+                    Some((group, key)) => record[group][key].as_u64().unwrap_or(0),
+                    // total violations
+                    None if name == "violations" => record["violations"]
+                        .as_object()
+                        .map_or(0, |m| m.values().filter_map(|v| v.as_u64()).sum()),
+                    None => record[&name].as_u64().unwrap_or(0),
+                })
+                .collect();
+            (name, values)
+        })
+        .collect()
+}
+
+pub fn trend(format: Format) -> Result<(), String> {
+    let history = read_history()?;
+    if history.is_empty() {
+        return Err("no snapshots yet, run `para snapshot` first".to_string());
+    }
+    let metrics = metrics(&history);
+
+    if format != Format::Text {
+        let rows = metrics.into_iter()
+            .map(|(name, values)| vec![
+                json!(name),
+                json!(values[0]),
+                json!(values[values.len() - 1]),
+                json!(values[values.len() - 1] as i64 - values[0] as i64),
+            ])
+            .collect();
+        output::print_records(format, &["metric", "first", "last", "change"], rows);
+        return Ok(());
+    }
+
+    let time = |record: &Value| record["time"]
+        .as_str()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map_or("?".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string());
+    println!("{}", format!(
+        "{} snapshots, {} to {}",
+        history.len(),
+        time(&history[0]),
+        time(&history[history.len() - 1]),
+    ).italic());
+    for (name, values) in metrics {
+        let first = values[0];
+        let last = values[values.len() - 1];
+        let show = |v: u64| match &name[..] {
+            "bytes" => format_bytes(v),
+            _ => v.to_string(),
+        };
+        let difference = match last >= first {
+            true => format!("+{}", show(last - first)),
+            false => format!("-{}", show(first - last)),
+        };
+        let change = match last.cmp(&first) {
+            Ordering::Equal => "=".normal(),
+            // fewer violations is an improvement, anything else is just growth
+            Ordering::Less if name.starts_with("violations") => difference.green(),
+            Ordering::Greater if name.starts_with("violations") => difference.red(),
+            _ => difference.yellow(),
+        };
+        println!(
+            "{:32} {:>10} -> {:<10} {:>10} {}",
+            name.green(),
+            show(first),
+            show(last),
+            change,
+            sparkline(&values),
+        );
+    }
+    Ok(())
+}
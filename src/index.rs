@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use colored::Colorize;
+
+use crate::activity::{format_time, last_modified};
+use crate::output::{html_escape, html_page};
+use crate::project;
+use crate::search::get_module_tags;
+use crate::walk::Tree;
+use crate::{get_home_path, get_root_paths};
+
+/// generated index files, allowed in PARA_HOME alongside the roots
+pub const INDEX_FILES: [&str; 2] = ["INDEX.md", "INDEX.html"];
+
+/// what the index shows for each module
+struct ModuleSummary {
+    name: String,
+    /// README.md relative to the index file
+    readme: String,
+    tags: Vec<String>,
+    details: Vec<String>,
+    summary: Option<String>,
+}
+
+/// first paragraph of the module's README.md, after any headings
+pub fn first_paragraph(module: &Path) -> Option<String> {
+    let readme = fs::read_to_string(module.join("README.md")).ok()?;
+    let paragraph: Vec<&str> = readme.lines()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty() || line.starts_with('#'))
+        .take_while(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    (!paragraph.is_empty()).then(|| paragraph.join(" "))
+}
+
+/// `path` relative to the directory `base`, both absolute
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[common..]);
+    relative
+}
+
+/// modules grouped by root, in name order, with links relative to `index_dir`
+fn summarise(index_dir: &Path) -> Vec<(String, Vec<ModuleSummary>)> {
+    let tree = Tree::scan();
+    let modules = tree.by_module();
+    let home = get_home_path();
+    // compare real paths, in case PARA_HOME is reached through a symlink
+    let real_home = home.canonicalize().unwrap_or(home.clone());
+    get_root_paths().iter()
+        .map(|root| {
+            let mut paths: Vec<&&Path> = modules.keys()
+                .filter(|m| m.parent() == Some(root))
+                .collect();
+            paths.sort();
+            let summaries = paths.into_iter()
+                .map(|module| {
+                    let mut details = vec![];
+                    if let Some(status) = project::get_module_status(module) {
+                        details.push(status.to_string());
+                    }
+                    if let Some(due) = project::get_module_due(module) {
+                        details.push(format!("due {}", due));
+                    }
+                    if let Some(time) = last_modified(&modules[*module]) {
+                        details.push(format!("last active {}", format_time(time)));
+                    }
+                    ModuleSummary {
+                        name: module.file_name().unwrap().to_str().unwrap().to_string(),
+                        readme: relative_to(
+                            &real_home.join(module.strip_prefix(&home).unwrap()).join("README.md"),
+                            index_dir,
+                        ).display().to_string(),
+                        tags: get_module_tags(module),
+                        details,
+                        summary: first_paragraph(module),
+                    }
+                })
+                .collect();
+            (root.file_name().unwrap().to_str().unwrap().to_string(), summaries)
+        })
+        .collect()
+}
+
+fn generated() -> String {
+    format!("generated {} by `para index-md`", chrono::Local::now().format("%Y-%m-%d %H:%M"))
+}
+
+fn render_markdown(roots: &[(String, Vec<ModuleSummary>)]) -> String {
+    let mut md = format!("# PARA index\n\n_{}_\n", generated());
+    for (root, modules) in roots {
+        md += &format!("\n## {} ({})\n", root, modules.len());
+        for module in modules {
+            md += &format!("\n### [{}]({})\n", module.name, module.readme.replace(' ', "%20"));
+            let mut line: Vec<String> = module.tags.iter().map(|t| format!("`{}`", t)).collect();
+            line.extend(module.details.iter().cloned());
+            if !line.is_empty() {
+                md += &format!("{}\n", line.join(" · "));
+            }
+            if let Some(summary) = &module.summary {
+                md += &format!("\n{}\n", summary);
+            }
+        }
+    }
+    md
+}
+
+fn render_html(roots: &[(String, Vec<ModuleSummary>)]) -> String {
+    let mut html = String::new();
+    html += &format!("<p><em>{}</em></p>\n", html_escape(&generated()));
+    for (root, modules) in roots {
+        html += &format!("<h2>{} ({})</h2>\n", html_escape(root), modules.len());
+        for module in modules {
+            html += &format!(
+                "<h3><a href=\"{}\">{}</a></h3>\n",
+                html_escape(&module.readme.replace(' ', "%20")),
+                html_escape(&module.name),
+            );
+            let mut line: Vec<String> = module.tags.iter()
+                .map(|t| format!("<span class=\"tag\">{}</span>", html_escape(t)))
+                .collect();
+            line.extend(module.details.iter().map(|d| html_escape(d)));
+            if !line.is_empty() {
+                html += &format!("<div class=\"details\">{}</div>\n", line.join(" · "));
+            }
+            if let Some(summary) = &module.summary {
+                html += &format!("<p>{}</p>\n", html_escape(summary));
+            }
+        }
+    }
+    html_page("PARA index", &html)
+}
+
+/// write an index of every module to PARA_HOME (or `output`), as markdown
+/// or html
+pub fn write_index(html: bool, output: Option<PathBuf>) -> Result<(), String> {
+    let output = output.unwrap_or(get_home_path().join(match html {
+        true => INDEX_FILES[1],
+        false => INDEX_FILES[0],
+    }));
+    let index_dir = std::path::absolute(&output)
+        .map_err(|e| e.to_string())?
+        .parent()
+        .ok_or("invalid output path")?
+        .canonicalize()
+        .map_err(|e| format!("couldn't write {}: {}", output.display(), e))?;
+    let roots = summarise(&index_dir);
+    let contents = match html {
+        true => render_html(&roots),
+        false => render_markdown(&roots),
+    };
+    fs::write(&output, contents).map_err(|e| e.to_string())?;
+    eprintln!("{}", format!("wrote {}", output.display()).green().italic());
+    Ok(())
+}
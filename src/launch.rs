@@ -1,50 +1,341 @@
-use std::{env, path::{Path, PathBuf}, process::Command};
+use std::{path::{Path, PathBuf}, process::{Command, ExitStatus}};
 use colored::Colorize;
 
-use crate::read_yaml;
+use crate::{config, get_home_path, git, read_yaml, tracking};
 
-pub fn open(module: &PathBuf) -> Result<(), String> {
+/// what `para open` leaves you in once the module is open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminal {
+    /// a nested zsh
+    Shell,
+    /// a tmux session named after the module
+    Tmux,
+    /// a zellij session named after the module
+    Zellij,
+}
+
+impl Terminal {
+    /// config.yaml `terminal`, a nested zsh by default
+    #[must_use]
+    pub fn from_config() -> Terminal {
+        match config::read_config()
+            .and_then(|c| c["terminal"].as_str().map(|s| s.to_string()))
+            .as_deref() {
+            Some("tmux") => Terminal::Tmux,
+            Some("zellij") => Terminal::Zellij,
+            _ => Terminal::Shell,
+        }
+    }
+}
+
+pub fn open(module: &PathBuf, terminal: Terminal) -> Result<(), String> {
     // print module path to std for "goto"/"cd" like command
     eprintln!("{}", format!(
         "opening: {}",
         module.file_name().unwrap().to_str().unwrap(),
     ).green().italic());
-    
+    let start = chrono::Local::now();
+    if let Err(e) = tracking::record_open(module) {
+        eprintln!("{}", e.red().italic());
+    }
+
+    run_hook("on_open", module)?;
+    git::init_repos(module)?;
     if let Some(yaml) = read_yaml(module) {
-        let cmd = yaml["open"]
-            .as_sequence()
-            .map(|s| s.iter()
-                .map(|x| x.as_str().map(|s| s.to_string()))
-                .collect::<Vec<Option<String>>>()
-            );
-        if let Some(cmd) = cmd {
-            // command sequence exists
-            let mut command: Command;
-            if let Some(root_cmd) = &cmd[0] {
-                command = Command::new(root_cmd);
-            } else {
-                return Err("couldn't parse command argument".to_string());
+        match get_action(&yaml, "open") {
+            Some(cmd) => {
+                run_command(cmd, module, "`open` command")?;
+            },
+            None => eprintln!("{}", "no `open` command in para.yaml".red().italic()),
+        }
+    }
+    
+    match terminal {
+        Terminal::Shell => {
+            Command::new("zsh")
+            .current_dir(module)
+            .envs(para_env(module)?)
+            .status().or(Err("couldn't start zsh"))?;
+            // the shell has exited, so the session is over
+            tracking::record_session(module, start)?;
+            run_hook("on_close", module)?;
+        },
+        // a tmux/zellij session outlives `para open`: switching to it returns
+        // straight away and detaching leaves it running, so it isn't timed
+        // and `on_close` isn't run
+        Terminal::Tmux => open_tmux(module)?,
+        Terminal::Zellij => open_zellij(module)?,
+    }
+    Ok(())
+}
+
+/// tmux/zellij session name for a module, which can't contain `.` or `:`
+fn session_name(module: &Path) -> String {
+    module.file_name().unwrap().to_str().unwrap().replace(['.', ':'], "_")
+}
+
+/// run tmux, returning its stdout
+fn tmux(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("tmux")
+        .args(args)
+        .output()
+        .or(Err("couldn't start tmux"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tmux {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// attach to the module's tmux session, creating it first if need be, with
+/// the windows from para.yaml:
+/// ```yaml
+/// tmux:
+///   windows:
+///     - name: edit
+///       panes: ["nvim ."]
+///     - name: serve
+///       layout: even-horizontal
+///       panes: ["npm run dev", "npm test -- --watch"]
+/// ```
+fn open_tmux(module: &Path) -> Result<(), String> {
+    let name = session_name(module);
+    let target = format!("={}", name);
+    let dir = module.display().to_string();
+
+    if tmux(&["has-session", "-t", &target]).is_err() {
+        let env: Vec<String> = para_env(module)?
+            .into_iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        let mut new_session = vec!["new-session", "-d", "-P", "-F", "#{window_id}", "-s", &name, "-c", &dir];
+        for var in &env {
+            new_session.extend(["-e", var]);
+        }
+        let mut window = tmux(&new_session)?;
+
+        let windows = read_yaml(module)
+            .and_then(|yaml| yaml["tmux"]["windows"].as_sequence().cloned())
+            .unwrap_or_default();
+        for (i, layout) in windows.iter().enumerate() {
+            if i > 0 {
+                let mut new_window = vec!["new-window", "-d", "-P", "-F", "#{window_id}", "-t", &target, "-c", &dir];
+                for var in &env {
+                    new_window.extend(["-e", var]);
+                }
+                window = tmux(&new_window)?;
+            }
+            if let Some(window_name) = layout["name"].as_str() {
+                tmux(&["rename-window", "-t", &window, window_name])?;
             }
-            for arg in cmd[1..].iter() {
-                if let Some(arg) = arg {
-                    command.arg(arg);
-                } else {
-                    return Err("failed to parse command arguments in para.yaml".to_string())
+            let panes = layout["panes"].as_sequence().cloned().unwrap_or_default();
+            for (j, pane) in panes.iter().enumerate() {
+                let pane_id = match j {
+                    0 => window.clone(),
+                    _ => tmux(&["split-window", "-d", "-P", "-F", "#{pane_id}", "-t", &window, "-c", &dir])?,
+                };
+                if let Some(cmd) = pane.as_str() {
+                    tmux(&["send-keys", "-t", &pane_id, cmd, "Enter"])?;
                 }
             }
-            command.current_dir(module);
-            command.status().or(Err("failed to spawn `open` command from para.yaml"))?;
-        } else {
-            eprintln!("{}", "couldn't parse para.yaml `open` as command sequence".red().italic());
+            if let Some(tmux_layout) = layout["layout"].as_str() {
+                tmux(&["select-layout", "-t", &window, tmux_layout])?;
+            }
+        }
+    }
+
+    // inside tmux already, switch rather than nest
+    let attach = match std::env::var_os("TMUX") {
+        Some(_) => "switch-client",
+        None => "attach-session",
+    };
+    let status = Command::new("tmux")
+        .args([attach, "-t", &target])
+        .status()
+        .or(Err("couldn't start tmux"))?;
+    if !status.success() {
+        return Err(format!("tmux {} failed", attach));
+    }
+    Ok(())
+}
+
+/// attach to the module's zellij session, creating it (with the layout file
+/// from para.yaml `zellij: layout:`, if any) when it doesn't exist
+fn open_zellij(module: &Path) -> Result<(), String> {
+    let name = session_name(module);
+    let sessions = Command::new("zellij")
+        .args(["list-sessions", "--short", "--no-formatting"])
+        .output()
+        .or(Err("couldn't start zellij"))?;
+    let exists = String::from_utf8_lossy(&sessions.stdout)
+        .lines()
+        .any(|s| s.trim() == name);
+
+    let mut command = Command::new("zellij");
+    command.current_dir(module).envs(para_env(module)?);
+    if exists {
+        command.args(["attach", &name]);
+    } else {
+        command.args(["--session", &name]);
+        if let Some(layout) = read_yaml(module)
+            .and_then(|yaml| yaml["zellij"]["layout"].as_str().map(|s| s.to_string())) {
+            command.arg("--layout").arg(module.join(layout));
         }
-        if let Some(git) = yaml["git"].as_str() {
-            init_git(git, module)?;
+    }
+    let status = command.status().or(Err("couldn't start zellij"))?;
+    if !status.success() {
+        return Err("zellij failed".to_string());
+    }
+    Ok(())
+}
+
+/// environment for commands run on the module's behalf: PARA_* variables,
+/// then para.yaml's `env_file` (dotenv syntax), then its `env` map
+fn para_env(module: &Path) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![
+        ("PARA_MODULE".to_string(), module.display().to_string()),
+        ("PARA_ROOT".to_string(), module.parent().unwrap().display().to_string()),
+        ("PARA_HOME".to_string(), get_home_path().display().to_string()),
+    ];
+    let Some(yaml) = read_yaml(module) else {
+        return Ok(vars);
+    };
+    if let Some(env_file) = yaml["env_file"].as_str() {
+        let env_file = module.join(env_file);
+        let entries = dotenvy::from_path_iter(&env_file)
+            .map_err(|e| format!("couldn't read {}: {}", env_file.display(), e))?;
+        for entry in entries {
+            vars.push(entry.map_err(|e| format!("couldn't parse {}: {}", env_file.display(), e))?);
         }
     }
-    
-    Command::new("zsh")
-    .current_dir(module)
-    .status().or(Err("couldn't start zsh"))?;
+    if let Some(env) = yaml["env"].as_mapping() {
+        for (key, value) in env {
+            let key = key.as_str().ok_or("para.yaml `env` keys must be strings")?;
+            let value = match value {
+                serde_yaml::Value::String(s) => s.clone(),
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                _ => return Err(format!("para.yaml `env` value for {} must be a scalar", key)),
+            };
+            vars.push((key.to_string(), value));
+        }
+    }
+    Ok(vars)
+}
+
+/// expand `{module}`, `{root}`, `{home}` and `{readme}` in a para.yaml command,
+/// passing each path through `quote`
+fn expand_placeholders(s: &str, module: &Path, quote: fn(&str) -> String) -> String {
+    let path = |p: &Path| quote(&p.display().to_string());
+    s.replace("{module}", &path(module))
+        .replace("{root}", &path(module.parent().unwrap()))
+        .replace("{home}", &path(&get_home_path()))
+        .replace("{readme}", &path(&module.join("README.md")))
+}
+
+/// single quote `s` for sh
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// build a para.yaml command to run in `module`, either a sequence like
+/// `["code", "."]` or a shell string like `"make && ./run"` run with `sh -c`
+fn build_command(cmd: &serde_yaml::Value, module: &Path) -> Result<Command, String> {
+    let cmd = match cmd {
+        serde_yaml::Value::String(shell) => vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            expand_placeholders(shell, module, shell_quote),
+        ],
+        cmd => cmd
+            .as_sequence()
+            .ok_or("not a command sequence or string")?
+            .iter()
+            .map(|x| x.as_str().map(|s| expand_placeholders(s, module, |p| p.to_string())))
+            .collect::<Option<Vec<String>>>()
+            .ok_or("failed to parse command arguments")?,
+    };
+    let (program, args) = cmd.split_first().ok_or("empty command")?;
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(module)
+        .envs(para_env(module)?);
+    Ok(command)
+}
+
+/// run a lifecycle hook (`on_open`, `on_close`, `on_archive`, `on_new`) from
+/// the module's para.yaml, falling back to the default in config.yaml
+pub fn run_hook(hook: &str, module: &Path) -> Result<(), String> {
+    let cmd = read_yaml(module)
+        .map(|yaml| yaml[hook].clone())
+        .filter(|cmd| !cmd.is_null())
+        .or_else(|| config::read_config()
+            .map(|c| c[hook].clone())
+            .filter(|cmd| !cmd.is_null())
+        );
+    let Some(cmd) = cmd else {
+        return Ok(());
+    };
+    eprintln!("{}", format!("running {}", hook).green().italic());
+    let status = run_command(&cmd, module, &format!("`{}` hook", hook))?;
+    if !status.success() {
+        return Err(format!("`{}` hook failed", hook));
+    }
+    Ok(())
+}
+
+/// run a para.yaml command in `module`, `name` describes it in errors
+fn run_command(
+    cmd: &serde_yaml::Value,
+    module: &Path,
+    name: &str,
+) -> Result<ExitStatus, String> {
+    build_command(cmd, module)
+        .map_err(|e| format!("couldn't parse {}: {}", name, e))?
+        .status()
+        .or(Err(format!("failed to spawn {}", name)))
+}
+
+/// command for a named action, `open` falls back to the top level `open` key
+fn get_action<'a>(yaml: &'a serde_yaml::Value, action: &str) -> Option<&'a serde_yaml::Value> {
+    let cmd = &yaml["actions"][action];
+    if !cmd.is_null() {
+        return Some(cmd);
+    }
+    let cmd = &yaml[action];
+    (action == "open" && !cmd.is_null()).then_some(cmd)
+}
+
+/// names of the actions `para run` accepts for `module`
+pub fn list_actions(module: &Path) -> Vec<String> {
+    let Some(yaml) = read_yaml(module) else {
+        return vec![];
+    };
+    let mut actions: Vec<String> = yaml["actions"]
+        .as_mapping()
+        .map(|actions| actions.keys()
+            .filter_map(|k| k.as_str().map(|s| s.to_string()))
+            .collect()
+        )
+        .unwrap_or_default();
+    if !actions.iter().any(|a| a == "open") && get_action(&yaml, "open").is_some() {
+        actions.insert(0, "open".to_string());
+    }
+    actions
+}
+
+pub fn run_action(module: &Path, action: &str) -> Result<(), String> {
+    let yaml = read_yaml(module).ok_or("couldn't read para.yaml")?;
+    let cmd = get_action(&yaml, action)
+        .ok_or(format!("no `{}` action in para.yaml", action))?;
+    let status = run_command(cmd, module, &format!("`{}` action", action))?;
+    if !status.success() {
+        return Err(format!("`{}` action failed", action));
+    }
     Ok(())
 }
 
@@ -55,47 +346,89 @@ pub fn edit_note(note: PathBuf) -> Result<(), String> {
     Ok(())
 }
 
-fn init_git(git: &str, module: &Path) -> Result<(), String> {
-    // get git repo name (will be dir name)
-    let name = match git.split('/').last() {
-        Some(n) => n.trim_end_matches(".git"),
-        None => return Err("para.yaml git url invalid".to_string()),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
 
-    // git url is defined, confirm that no dir with that name exists yet
-    if module.join(name).exists() {
-        // already exists, no problem.
-        return Ok(());
+    /// a module in a root, both with awkward names
+    fn awkward_module() -> PathBuf {
+        let module = crate::test_home().join("it's a root").join("my 'quoted' module");
+        fs::create_dir_all(&module).unwrap();
+        module
     }
 
-    // check if repo in downloads, if not, get it
-    let original = Path::new(
-        &env::var("HOME").expect("HOME env var not defined")
-    ).to_path_buf().join("Downloads").join(name);
-    if !original.exists() {
-        // doesn't exist, clone it:
-        if let Ok(status) = Command::new("git")
-        .arg("clone")
-        .arg(git)
-        .arg(&original)
-        .status() {
-            if !status.success() {
-                return Err("git clone failed".to_string());
-            }
-        } else {
-            return Err(
-                "failed to start git".to_string()
-            );
-        }
+    fn stdout(mut command: Command) -> String {
+        let output = command.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
     }
-    // now there is a correctly named directory in the downlaods folder,
-    // hopefully the git repo but if it's not then that's fine, whatever.
 
-    // make symbolic link here linking to cloned repo
-    if let Err(e) = std::os::unix::fs::symlink(original, module.join(name)) {
-        return Err(e.to_string())
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_quote("it's a $HOME `x`")))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "it's a $HOME `x`");
     }
 
-    // done!
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn expand_placeholders_quotes_each_path() {
+        let module = Path::new("/p/root dir/mod's");
+        let home = crate::test_home();
+        let expanded = expand_placeholders("{module} {root} {readme} {home}", module, shell_quote);
+        assert_eq!(expanded, format!(
+            "'/p/root dir/mod'\\''s' '/p/root dir' '/p/root dir/mod'\\''s/README.md' '{}'",
+            home.display(),
+        ));
+        let unquoted = expand_placeholders("{module}/x", module, |p| p.to_string());
+        assert_eq!(unquoted, "/p/root dir/mod's/x");
+    }
+
+    #[test]
+    fn shell_string_commands_see_paths_intact() {
+        let module = awkward_module();
+        let cmd = serde_yaml::Value::String("printf '%s\\n' {module} {root} {readme}".to_string());
+        assert_eq!(stdout(build_command(&cmd, &module).unwrap()), format!(
+            "{}\n{}\n{}\n",
+            module.display(),
+            module.parent().unwrap().display(),
+            module.join("README.md").display(),
+        ));
+    }
+
+    #[test]
+    fn sequence_commands_get_paths_as_single_arguments() {
+        let module = awkward_module();
+        let cmd: serde_yaml::Value = serde_yaml::from_str("[printf, '%s|', '{module}', 'in {root}']").unwrap();
+        assert_eq!(stdout(build_command(&cmd, &module).unwrap()), format!(
+            "{}|in {}|",
+            module.display(),
+            module.parent().unwrap().display(),
+        ));
+    }
+
+    #[test]
+    fn commands_run_in_the_module_with_para_env() {
+        let module = awkward_module();
+        let cmd = serde_yaml::Value::String("pwd; printf '%s\\n' \"$PARA_MODULE\"".to_string());
+        assert_eq!(
+            stdout(build_command(&cmd, &module).unwrap()),
+            format!("{0}\n{0}\n", module.display()),
+        );
+    }
+
+    #[test]
+    fn invalid_commands_are_rejected() {
+        let module = awkward_module();
+        for cmd in ["[]", "[printf, [nested]]", "{ a: b }"] {
+            let cmd: serde_yaml::Value = serde_yaml::from_str(cmd).unwrap();
+            assert!(build_command(&cmd, &module).is_err());
+        }
+    }
+}
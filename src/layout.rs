@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use colored::Colorize;
 
+use crate::launch;
+
 pub fn new(module: PathBuf) -> Result<(), String> {
     match fs::DirBuilder::new().create(&module) {
         Ok(()) => eprintln!("{}", "created module".green().italic()),
@@ -33,6 +35,7 @@ pub fn new(module: PathBuf) -> Result<(), String> {
         }
     }
 
+    launch::run_hook("on_new", &module)?;
     Ok(())
 }
 
@@ -65,5 +68,9 @@ pub fn mv(module: PathBuf, root: PathBuf) -> Result<(), String> {
             ).green().italic()
         );
     }
+
+    if root.file_name().is_some_and(|name| name == "archive") {
+        launch::run_hook("on_archive", &destination)?;
+    }
     Ok(())
 }
\ No newline at end of file
@@ -3,10 +3,24 @@ use std::path::{Path,PathBuf};
 use colored::Colorize;
 use colored::CustomColor;
 use std::fs;
+use serde_json::json;
+use output::Format;
+pub mod activity;
 pub mod audit;
+pub mod config;
+pub mod git;
+pub mod graph;
 pub mod search;
+pub mod history;
+pub mod index;
 pub mod launch;
 pub mod layout;
+pub mod links;
+pub mod output;
+pub mod project;
+pub mod report;
+pub mod tracking;
+pub mod walk;
 
 #[must_use]
 pub fn get_home_path() -> PathBuf {
@@ -56,33 +70,106 @@ pub fn eprint_modules(modules: Vec<PathBuf>) {
     }
 }
 
-pub fn print_modules(modules: Vec<PathBuf>, colorised: bool) {
+pub fn print_modules(modules: Vec<PathBuf>, format: Format) {
+    if format != Format::Text {
+        let rows = modules.iter()
+            .map(|module| vec![
+                json!(module.file_name().unwrap().to_str().unwrap()),
+                json!(module.parent().unwrap().file_name().unwrap().to_str().unwrap()),
+                json!(module.display().to_string()),
+            ])
+            .collect();
+        output::print_records(format, &["module", "root", "path"], rows);
+        return;
+    }
     for module in modules {
-        if colorised {
-            println!(
-                "{}/{}/{}",
-                module.parent().unwrap()
-                .parent().unwrap()
-                .display().to_string()
-                .custom_color(CustomColor{r:100,g:100,b:100}),
-                module.parent().unwrap()
-                .file_name().unwrap()
-                .to_str().unwrap().to_string()
-                .custom_color(CustomColor{r:100,g:140,b:100}),
-                module.file_name().unwrap()
-                .to_str().unwrap().to_string()
-                .custom_color(CustomColor{r:100,g:255,b:100}),
-            );
-        } else {
-            println!("{}", module.display());
-        }
+        println!(
+            "{}/{}/{}",
+            module.parent().unwrap()
+            .parent().unwrap()
+            .display().to_string()
+            .custom_color(CustomColor{r:100,g:100,b:100}),
+            module.parent().unwrap()
+            .file_name().unwrap()
+            .to_str().unwrap().to_string()
+            .custom_color(CustomColor{r:100,g:140,b:100}),
+            module.file_name().unwrap()
+            .to_str().unwrap().to_string()
+            .custom_color(CustomColor{r:100,g:255,b:100}),
+        );
+    }
+}
+
+pub fn print_deadlines(deadlines: Vec<(PathBuf, chrono::NaiveDate)>, format: Format) {
+    if format != Format::Text {
+        let rows = deadlines.iter()
+            .map(|(module, due)| vec![
+                json!(due.to_string()),
+                json!(project::get_module_status(module).map(|s| s.to_string())),
+                json!(module.display().to_string()),
+            ])
+            .collect();
+        output::print_records(format, &["due", "status", "path"], rows);
+        return;
+    }
+    let today = project::today();
+    for (module, due) in deadlines {
+        let due_str = due.to_string();
+        println!(
+            "{} {}",
+            match project::get_module_status(&module) {
+                Some(project::Status::Done) => due_str.green(),
+                _ if due < today => due_str.red(),
+                _ => due_str.yellow(),
+            },
+            module.display(),
+        );
+    }
+}
+
+/// human readable byte count, e.g. 1.5 MiB
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
     }
 }
 
+/// duration as hours and minutes, e.g. 12h05m
+#[must_use]
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
 pub fn print_count(item: &str, count: u32) {
     println!("{:5} {}", count.to_string().yellow(), item.green());
 }
 
+/// print `(item, count)` rows, `column` names the items for csv/json output
+pub fn print_counts(column: &str, counts: Vec<(String, u32)>, format: Format) {
+    if format == Format::Text {
+        counts.iter().for_each(|(item, count)| print_count(item, *count));
+    } else {
+        let rows = counts.into_iter()
+            .map(|(item, count)| vec![json!(item), json!(count)])
+            .collect();
+        output::print_records(format, &[column, "count"], rows);
+    }
+}
+
+pub fn print_size(item: &str, bytes: u64) {
+    println!("{:>10} {}", format_bytes(bytes).yellow(), item.green());
+}
+
 #[must_use]
 pub fn read_yaml(
     module: &Path,
@@ -94,4 +181,12 @@ pub fn read_yaml(
         }
     }
     None
-}
\ No newline at end of file
+}
+/// PARA_HOME for unit tests. Tests share the process environment, so every
+/// test needing PARA_HOME uses this same path.
+#[cfg(test)]
+pub(crate) fn test_home() -> PathBuf {
+    let home = env::temp_dir().join("para-audit-test-home");
+    env::set_var("PARA_HOME", &home);
+    home
+}
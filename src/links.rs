@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use rayon::prelude::*;
+use regex::Regex;
+use serde_json::json;
+
+use crate::get_module_paths;
+use crate::output::{self, Format};
+use crate::walk::Tree;
+
+/// a `[[module_name]]` reference in a module's markdown
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// markdown file containing the link
+    pub file: PathBuf,
+    /// module containing the file
+    pub module: PathBuf,
+    /// module name linked to, may not exist
+    pub target: String,
+}
+
+/// module names linked to from markdown. Links may carry a heading or
+/// alias, as in `[[module#heading]]` or `[[module|text]]`.
+pub fn parse_links(contents: &str) -> Vec<String> {
+    let re = Regex::new(r"\[\[([^\]\[|#]+)(?:[|#][^\]\[]*)?\]\]").unwrap();
+    re.captures_iter(contents)
+        .map(|c| c[1].trim().to_string())
+        .collect()
+}
+
+/// every wiki-style link in the markdown files of every module
+pub fn get_links(tree: &Tree) -> Vec<Link> {
+    tree.entries
+        .par_iter()
+        .filter(|e| e.is_file() && e.extension() == "md")
+        .filter_map(|e| Some((e, e.module.as_ref()?, fs::read_to_string(&e.path).ok()?)))
+        .flat_map_iter(|(entry, module, contents)| parse_links(&contents)
+            .into_iter()
+            .map(|target| Link {
+                file: entry.path.clone(),
+                module: module.clone(),
+                target,
+            })
+            .collect::<Vec<Link>>()
+        )
+        .collect()
+}
+
+/// links pointing at modules that don't exist
+pub fn get_broken_links(tree: &Tree) -> Vec<Link> {
+    let names: HashSet<String> = get_module_paths()
+        .iter()
+        .map(|m| m.file_name().unwrap().to_str().unwrap().to_string())
+        .collect();
+    get_links(tree)
+        .into_iter()
+        .filter(|link| !names.contains(&link.target))
+        .collect()
+}
+
+/// outgoing links from `module`, and links to it from other modules
+pub fn print_links(module: &Path, format: Format) {
+    let name = module.file_name().unwrap().to_str().unwrap();
+    let links = get_links(&Tree::scan());
+    let mut rows: Vec<(&str, String, &Path)> = vec![];
+    for link in &links {
+        if link.module == module {
+            rows.push(("outgoing", link.target.clone(), &link.file));
+        }
+    }
+    for link in &links {
+        if link.target == name && link.module != module {
+            rows.push((
+                "backlink",
+                link.module.file_name().unwrap().to_str().unwrap().to_string(),
+                &link.file,
+            ));
+        }
+    }
+
+    if format != Format::Text {
+        let rows = rows.into_iter()
+            .map(|(direction, module, file)| vec![
+                json!(direction),
+                json!(module),
+                json!(file.display().to_string()),
+            ])
+            .collect();
+        output::print_records(format, &["direction", "module", "file"], rows);
+        return;
+    }
+    let mut direction = "";
+    for (d, linked, file) in rows {
+        if d != direction {
+            println!("{}", match d {
+                "outgoing" => "links to",
+                _ => "linked from",
+            }.italic());
+            direction = d;
+        }
+        println!("  {} {}", linked.green(), file.display().to_string().custom_color(
+            colored::CustomColor { r: 100, g: 100, b: 100 }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_alias_and_heading_links() {
+        assert_eq!(
+            parse_links("see [[alpha]], [[beta|the b]] and [[gamma#setup]]"),
+            ["alpha", "beta", "gamma"],
+        );
+        assert_eq!(parse_links("[[delta#notes|read this]]"), ["delta"]);
+    }
+
+    #[test]
+    fn whitespace_is_trimmed() {
+        assert_eq!(parse_links("[[ alpha ]] [[beta |b]] [[\tgamma# x]]"), ["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn nested_and_empty_brackets() {
+        // only the innermost complete link counts
+        assert_eq!(parse_links("[[outer [[inner]] ]]"), ["inner"]);
+        assert_eq!(parse_links("[[[alpha]]]"), ["alpha"]);
+        assert!(parse_links("[[]] [[#heading]] [[|alias]] [single] [[unclosed").is_empty());
+    }
+
+    #[test]
+    fn links_across_lines() {
+        assert_eq!(parse_links("- [[alpha]]\n- [[beta]]\n"), ["alpha", "beta"]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::io::IsTerminal;
+use serde_json::Value;
+
+/// how listings and statistics are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// turn colour off when stdout isn't a terminal or NO_COLOR is set
+pub fn init_colour() {
+    if env::var_os("NO_COLOR").is_some() || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
+
+fn csv_field(value: &Value) -> String {
+    let s = plain(value);
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+fn tsv_field(value: &Value) -> String {
+    plain(value).replace(['\t', '\n'], " ")
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+/// write rows in a machine readable format, one object per row for json
+pub fn print_records(format: Format, columns: &[&str], rows: Vec<Vec<Value>>) {
+    match format {
+        Format::Json => {
+            let records: Vec<Value> = rows.into_iter()
+                .map(|row| Value::Object(
+                    columns.iter()
+                        .map(|c| c.to_string())
+                        .zip(row)
+                        .collect()
+                ))
+                .collect();
+            println!("{}", Value::Array(records));
+        },
+        Format::Csv | Format::Tsv => {
+            let (separator, field): (&str, fn(&Value) -> String) = match format {
+                Format::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+            println!("{}", columns.join(separator));
+            for row in rows {
+                println!("{}", row.iter().map(field).collect::<Vec<String>>().join(separator));
+            }
+        },
+        Format::Text => {
+            for row in rows {
+                println!("{}", row.iter().map(plain).collect::<Vec<String>>().join(" "));
+            }
+        },
+    }
+}
+
+/// escape text for inclusion in html
+#[must_use]
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// a self-contained html page around `body`, styled for `para index-md` and
+/// `para report`
+#[must_use]
+pub fn html_page(title: &str, body: &str) -> String {
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n",
+            "<title>{title}</title>\n<style>\n",
+            "body {{ font-family: sans-serif; max-width: 48em; margin: auto; padding: 1em; }}\n",
+            "table {{ border-collapse: collapse; }}\n",
+            "td, th {{ padding: 2px 8px; text-align: left; }}\n",
+            "td.number {{ text-align: right; }}\n",
+            "pre {{ background: #f4f4f4; padding: 4px; overflow-x: auto; }}\n",
+            ".tag {{ background: #e4efe4; border-radius: 3px; padding: 0 4px; }}\n",
+            ".details {{ color: #666; font-size: 90%; }}\n",
+            "</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        ),
+        title = html_escape(title),
+        body = body,
+    )
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::NaiveDate;
+
+use crate::read_yaml;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Active,
+    Paused,
+    Done,
+}
+
+impl FromStr for Status {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(Status::Active),
+            "paused" => Ok(Status::Paused),
+            "done" => Ok(Status::Done),
+            s => Err(format!("invalid status - {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Status::Active => "active",
+            Status::Paused => "paused",
+            Status::Done => "done",
+        })
+    }
+}
+
+#[must_use]
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
+
+pub fn get_module_status(module: &Path) -> Option<Status> {
+    read_yaml(module)?["status"]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+}
+
+pub fn get_module_due(module: &Path) -> Option<NaiveDate> {
+    read_yaml(module)?["due"]
+        .as_str()
+        .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+}
+
+pub fn sort_by_due(modules: Vec<PathBuf>) -> Vec<(PathBuf, NaiveDate)> {
+    // modules without a deadline are dropped
+    let mut deadlines: Vec<(PathBuf, NaiveDate)> = modules.into_iter()
+        .filter_map(|module| {
+            let due = get_module_due(&module)?;
+            Some((module, due))
+        })
+        .collect();
+    deadlines.sort_by_key(|(_, due)| *due);
+    deadlines
+}
+
+/// a module has an outcome if para.yaml has a non-empty `goal`/`outcome`,
+/// or README.md has a non-empty `## Goal` section
+pub fn has_outcome(module: &Path) -> bool {
+    if let Some(yaml) = read_yaml(module) {
+        if ["goal", "outcome"].iter().any(|key|
+            yaml[key].as_str().is_some_and(|s| !s.trim().is_empty())
+        ) {
+            return true;
+        }
+    }
+    let Ok(readme) = fs::read_to_string(module.join("README.md")) else {
+        return false;
+    };
+    let mut in_goal = false;
+    for line in readme.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            in_goal = line.trim_start_matches('#').trim().eq_ignore_ascii_case("goal")
+                && line.starts_with("## ");
+        } else if in_goal && !line.is_empty() {
+            return true;
+        }
+    }
+    false
+}
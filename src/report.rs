@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use colored::Colorize;
+
+use crate::activity::{format_time, recent_modules};
+use crate::output::{self, html_escape, html_page};
+use crate::walk::{total_size, Tree};
+use crate::{audit, format_bytes, get_home_path, get_root_paths, search};
+
+/// number of file extensions listed in the report's stats
+const TOP_EXTENSIONS: usize = 10;
+
+/// modules, files and disk usage per root, and the most common extensions
+fn stats_html(tree: &Tree) -> String {
+    let modules = tree.by_module();
+    let mut html = String::from(
+        "<table>\n<tr><th>root</th><th>modules</th><th>files</th><th>size</th></tr>\n"
+    );
+    for root in get_root_paths() {
+        let entries: Vec<_> = tree.entries.iter()
+            .filter(|e| e.path.starts_with(&root))
+            .collect();
+        html += &format!(
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td></tr>\n",
+            html_escape(root.file_name().unwrap().to_str().unwrap()),
+            modules.keys().filter(|m| m.parent() == Some(&root)).count(),
+            entries.iter().filter(|e| e.is_file()).count(),
+            format_bytes(total_size(entries.iter().copied())),
+        );
+    }
+    html += &format!(
+        "<tr><th>total</th><th class=\"number\">{}</th><th class=\"number\">{}</th><th class=\"number\">{}</th></tr>\n</table>\n",
+        modules.len(),
+        tree.entries.iter().filter(|e| e.is_file()).count(),
+        format_bytes(total_size(&tree.entries)),
+    );
+
+    let mut extensions: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in tree.entries.iter().filter(|e| e.is_file()) {
+        *extensions.entry(entry.extension()).or_insert(0) += 1;
+    }
+    let mut extensions: Vec<(String, usize)> = extensions.into_iter().collect();
+    extensions.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    html += "<h3>most common extensions</h3>\n<table>\n";
+    for (extension, count) in extensions.into_iter().take(TOP_EXTENSIONS) {
+        html += &format!(
+            "<tr><td>{}</td><td class=\"number\">{}</td></tr>\n",
+            html_escape(&extension), count,
+        );
+    }
+    html + "</table>\n"
+}
+
+/// every tag, sized by how many modules use it
+fn tag_cloud_html() -> Result<String, String> {
+    let mut tags = search::get_all_tags()?;
+    if tags.is_empty() {
+        return Ok("<p>no tags</p>\n".to_string());
+    }
+    tags.sort();
+    let max = tags.iter().map(|(_, count)| *count).max().unwrap_or(1) as f64;
+    let cloud: Vec<String> = tags.iter()
+        .map(|(tag, count)| format!(
+            "<span class=\"tag\" style=\"font-size: {:.0}%\" title=\"{} modules\">{}</span>",
+            80.0 + 120.0 * *count as f64 / max,
+            count,
+            html_escape(tag),
+        ))
+        .collect();
+    Ok(format!("<p style=\"line-height: 2\">{}</p>\n", cloud.join("\n")))
+}
+
+/// modules changed in the last `days` days
+fn recent_html(tree: &Tree, days: u64) -> String {
+    let recent = recent_modules(tree, days);
+    if recent.is_empty() {
+        return format!("<p>nothing changed in the last {} days</p>\n", days);
+    }
+    let home = get_home_path();
+    let mut html = String::from(
+        "<table>\n<tr><th>modified</th><th>changed files</th><th>module</th></tr>\n"
+    );
+    for (module, latest, changed) in recent {
+        html += &format!(
+            "<tr><td>{}</td><td class=\"number\">{}</td><td>{}</td></tr>\n",
+            format_time(latest),
+            changed,
+            html_escape(&module.strip_prefix(&home).unwrap_or(&module).display().to_string()),
+        );
+    }
+    html + "</table>\n"
+}
+
+/// write the audit, stats, tag cloud and recent activity to a single html file
+pub fn write_report(path: &Path, days: u64) -> Result<(), String> {
+    // walk PARA_HOME once, for every section
+    let tree = Tree::scan();
+    // violations are rendered with their Display impl, so without colour codes
+    colored::control::set_override(false);
+    let audit = audit::report_html(&tree);
+    colored::control::unset_override();
+    output::init_colour();
+
+    let mut body = format!(
+        "<p><em>{} generated {}</em></p>\n",
+        html_escape(&get_home_path().display().to_string()),
+        chrono::Local::now().format("%Y-%m-%d %H:%M"),
+    );
+    body += &format!("<h2>audit</h2>\n{}", audit);
+    body += &format!("<h2>stats</h2>\n{}", stats_html(&tree));
+    body += &format!("<h2>tags</h2>\n{}", tag_cloud_html()?);
+    body += &format!("<h2>recent activity</h2>\n{}", recent_html(&tree, days));
+
+    fs::write(path, html_page("PARA report", &body)).map_err(|e| e.to_string())?;
+    eprintln!("{}", format!("wrote {}", path.display()).green().italic());
+    Ok(())
+}
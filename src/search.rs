@@ -2,7 +2,7 @@ use std::path::{Path,PathBuf};
 use colored::Colorize;
 use std::collections::HashMap;
 
-use crate::{get_module_paths, get_root_paths, read_yaml};
+use crate::{get_module_paths, get_root_paths, read_yaml, tracking};
 
 pub fn search_modules(s: &str, precision: f64) -> Vec<PathBuf> {
     let module_paths = get_module_paths();
@@ -18,6 +18,7 @@ pub fn search_modules(s: &str, precision: f64) -> Vec<PathBuf> {
         hit
     }).collect();
     matches.append(&mut other_matches);
+    tracking::sort_by_frecency(&mut matches);
     matches
 }
 
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone};
+use colored::Colorize;
+use serde_json::json;
+
+use crate::{config, format_duration, search};
+use crate::output::{self, Format};
+
+fn get_opens_path() -> PathBuf {
+    config::get_state_dir().join("opens.jsonl")
+}
+
+fn get_sessions_path() -> PathBuf {
+    config::get_state_dir().join("sessions.jsonl")
+}
+
+fn module_name(module: &Path) -> String {
+    module.file_name().unwrap().to_str().unwrap().to_string()
+}
+
+/// append a line to a state file, creating the state directory if needed
+fn append_state(path: &Path, record: serde_json::Value) -> Result<(), String> {
+    fs::create_dir_all(config::get_state_dir()).or(Err("couldn't create state directory"))?;
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .or(Err(format!("couldn't open {}", path.display())))?;
+    writeln!(f, "{}", record).or(Err(format!("couldn't write to {}", path.display())))?;
+    Ok(())
+}
+
+/// remember that `module` was opened now. Modules are tracked by name, so
+/// moving a module between roots keeps its history.
+pub fn record_open(module: &Path) -> Result<(), String> {
+    append_state(&get_opens_path(), json!({
+        "time": Local::now().to_rfc3339(),
+        "module": module_name(module),
+    }))
+}
+
+/// (time, module name) of every recorded open, oldest first
+pub fn read_opens() -> Vec<(DateTime<Local>, String)> {
+    let Ok(opens) = fs::read_to_string(get_opens_path()) else {
+        return vec![];
+    };
+    opens.lines()
+        .filter_map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).ok()?;
+            let time = DateTime::parse_from_rfc3339(record["time"].as_str()?).ok()?;
+            Some((time.with_timezone(&Local), record["module"].as_str()?.to_string()))
+        })
+        .collect()
+}
+
+/// frequency weighted by recency, in the style of firefox's frecency
+fn frecency_scores() -> HashMap<String, u32> {
+    let now = Local::now();
+    let mut scores: HashMap<String, u32> = HashMap::new();
+    for (time, module) in read_opens() {
+        let weight = match (now - time).num_days() {
+            ..=3 => 100,
+            4..=14 => 70,
+            15..=31 => 50,
+            32..=90 => 30,
+            _ => 10,
+        };
+        *scores.entry(module).or_insert(0) += weight;
+    }
+    scores
+}
+
+/// stable sort, most frecent modules first
+pub fn sort_by_frecency(modules: &mut [PathBuf]) {
+    let scores = frecency_scores();
+    modules.sort_by_cached_key(|m|
+        std::cmp::Reverse(scores.get(&module_name(m)).copied().unwrap_or(0))
+    );
+}
+
+/// score a module needs before an ambiguous search resolves to it, e.g. two
+/// opens in the last few days or seven over the last three months
+const MIN_FRECENCY: u32 = 200;
+
+/// the module used clearly more than any of the others, if there is one: it
+/// needs at least MIN_FRECENCY and twice the score of the runner up
+pub fn most_frecent(modules: &[PathBuf]) -> Option<PathBuf> {
+    let scores = frecency_scores();
+    let mut ranked: Vec<(u32, &PathBuf)> = modules.iter()
+        .map(|m| (scores.get(&module_name(m)).copied().unwrap_or(0), m))
+        .collect();
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    match ranked[..] {
+        [(best, module), (second, _), ..] if best >= MIN_FRECENCY && best >= 2 * second => Some(module.clone()),
+        [(best, module)] if best >= MIN_FRECENCY => Some(module.clone()),
+        _ => None,
+    }
+}
+
+pub fn print_opens(days: u64, format: Format) {
+    // too far back to represent means no cutoff at all
+    let cutoff = i64::try_from(days).ok()
+        .and_then(Duration::try_days)
+        .and_then(|d| Local::now().checked_sub_signed(d));
+    let mut opens: Vec<(DateTime<Local>, String)> = read_opens()
+        .into_iter()
+        .filter(|(time, _)| cutoff.is_none_or(|cutoff| *time >= cutoff))
+        .collect();
+    opens.reverse();
+    if format != Format::Text {
+        let rows = opens.into_iter()
+            .map(|(time, module)| vec![json!(time.to_rfc3339()), json!(module)])
+            .collect();
+        output::print_records(format, &["opened", "module"], rows);
+        return;
+    }
+    for (time, module) in opens {
+        println!("{} {}", time.format("%Y-%m-%d %H:%M").to_string().yellow(), module.green());
+    }
+}
+
+/// record a work session on `module`, from `start` until now
+pub fn record_session(module: &Path, start: DateTime<Local>) -> Result<(), String> {
+    append_state(&get_sessions_path(), json!({
+        "module": module_name(module),
+        "start": start.to_rfc3339(),
+        "end": Local::now().to_rfc3339(),
+    }))
+}
+
+/// (module name, start, end) of every recorded session
+fn read_sessions() -> Vec<(String, DateTime<Local>, DateTime<Local>)> {
+    let Ok(sessions) = fs::read_to_string(get_sessions_path()) else {
+        return vec![];
+    };
+    let parse = |t: Option<&str>| DateTime::parse_from_rfc3339(t?)
+        .ok()
+        .map(|t| t.with_timezone(&Local));
+    sessions.lines()
+        .filter_map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).ok()?;
+            Some((
+                record["module"].as_str()?.to_string(),
+                parse(record["start"].as_str())?,
+                parse(record["end"].as_str())?,
+            ))
+        })
+        .collect()
+}
+
+/// local midnight at the start of this (monday based) week
+fn start_of_week() -> DateTime<Local> {
+    let today = Local::now().date_naive();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    Local.from_local_datetime(&monday.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .unwrap()
+}
+
+/// total time spent per module, and per tag, optionally this week only
+/// and/or only for modules tagged `tag`
+pub fn time_report(week: bool, tag: Option<&str>, format: Format) {
+    let since = week.then(start_of_week);
+    let mut modules: HashMap<String, Duration> = HashMap::new();
+    for (module, start, end) in read_sessions() {
+        if since.is_some_and(|since| end < since) {
+            continue;
+        }
+        // only count the part of a session inside the reported period
+        let start = since.map_or(start, |since| start.max(since));
+        *modules.entry(module).or_insert(Duration::zero()) += end - start;
+    }
+
+    let module_tags: HashMap<String, Vec<String>> = modules.keys()
+        .map(|m| (
+            m.clone(),
+            search::find_module(m).map_or(vec![], |p| search::get_module_tags(&p)),
+        ))
+        .collect();
+    if let Some(tag) = tag {
+        modules.retain(|m, _| module_tags[m].iter().any(|t| t == tag));
+    }
+    let mut tags: HashMap<String, Duration> = HashMap::new();
+    for (module, duration) in &modules {
+        for t in &module_tags[module] {
+            *tags.entry(t.clone()).or_insert(Duration::zero()) += *duration;
+        }
+    }
+
+    let sorted = |totals: HashMap<String, Duration>| {
+        let mut totals: Vec<(String, Duration)> = totals.into_iter().collect();
+        totals.sort_by_key(|(_, d)| std::cmp::Reverse(*d));
+        totals
+    };
+    let modules = sorted(modules);
+    let tags = sorted(tags);
+
+    if format != Format::Text {
+        let rows = modules.into_iter().map(|(name, d)| ("module", name, d))
+            .chain(tags.into_iter().map(|(name, d)| ("tag", name, d)))
+            .map(|(group, name, d)| vec![
+                json!(group),
+                json!(name),
+                json!((d.num_seconds() as f64 / 36.0).round() / 100.0),
+            ])
+            .collect();
+        output::print_records(format, &["group", "name", "hours"], rows);
+        return;
+    }
+    for (module, duration) in modules {
+        println!("{:>8} {}", format_duration(duration).yellow(), module.green());
+    }
+    if !tags.is_empty() {
+        println!("\n{}", "by tag".italic());
+    }
+    for (tag, duration) in tags {
+        println!("{:>8} {}", format_duration(duration).yellow(), tag.green());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{config, get_home_path, get_root_paths};
+
+/// gitignore-syntax file excluding paths from audits, stats and file counts
+pub const IGNORE_FILE: &str = ".paraignore";
+
+/// everything para needs to know about a single file or directory
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// module directory this entry belongs to (the module itself included)
+    pub module: Option<PathBuf>,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl Entry {
+    pub fn file_name(&self) -> &str {
+        self.path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir && !self.is_symlink
+    }
+
+    pub fn extension(&self) -> String {
+        match self.path.extension().and_then(|x| x.to_str()) {
+            Some(ext) => ext.to_string(),
+            None => "none".to_string(),
+        }
+    }
+}
+
+/// a single traversal of PARA_HOME, shared by audit rules and stats
+pub struct Tree {
+    pub entries: Vec<Entry>,
+}
+
+impl Tree {
+    /// walk PARA_HOME, reading directories in parallel. Like `visit_all`,
+    /// symlinks are recorded but not followed. Paths matched by a
+    /// `.paraignore` file in any directory above them are skipped, as is
+    /// para's own `.para` directory.
+    pub fn scan() -> Tree {
+        let roots = get_root_paths();
+        Tree {
+            entries: walk(&get_home_path(), None, &roots, &config::get_para_dir(), &[]),
+        }
+    }
+
+    /// entries grouped by the module they belong to
+    pub fn by_module(&self) -> HashMap<&Path, Vec<&Entry>> {
+        let mut modules: HashMap<&Path, Vec<&Entry>> = HashMap::new();
+        for entry in &self.entries {
+            if let Some(module) = &entry.module {
+                modules.entry(module).or_default().push(entry);
+            }
+        }
+        modules
+    }
+
+    /// groups of identical files of at least `min_size` bytes, found by
+    /// comparing sizes first and then hashing the candidates' contents
+    pub fn duplicate_files(&self, min_size: u64) -> Vec<(u64, Vec<PathBuf>)> {
+        let mut by_size: HashMap<u64, Vec<&Path>> = HashMap::new();
+        for entry in self.entries.iter().filter(|e| e.is_file() && e.size >= min_size) {
+            by_size.entry(entry.size).or_default().push(&entry.path);
+        }
+
+        let hashed: Vec<(u64, Vec<u8>, &Path)> = by_size
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .flat_map(|(size, paths)| paths.into_iter().map(move |p| (size, p)))
+            .collect::<Vec<(u64, &Path)>>()
+            .into_par_iter()
+            .filter_map(|(size, path)| Some((size, hash_file(path)?, path)))
+            .collect();
+
+        let mut groups: HashMap<(u64, Vec<u8>), Vec<PathBuf>> = HashMap::new();
+        for (size, hash, path) in hashed {
+            groups.entry((size, hash)).or_default().push(path.to_path_buf());
+        }
+        let mut duplicates: Vec<(u64, Vec<PathBuf>)> = groups
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|((size, _), mut paths)| {
+                paths.sort();
+                (size, paths)
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.1.cmp(&b.1));
+        duplicates
+    }
+}
+
+/// bytes used by the regular files among `entries`
+pub fn total_size<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> u64 {
+    entries.into_iter()
+        .filter(|e| e.is_file())
+        .map(|e| e.size)
+        .sum()
+}
+
+fn hash_file(path: &Path) -> Option<Vec<u8>> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_vec())
+}
+
+fn read_ignore_file(dir: &Path) -> Option<Arc<Gitignore>> {
+    let file = dir.join(IGNORE_FILE);
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&file) {
+        eprintln!("couldn't parse {}: {}", file.display(), e);
+    }
+    builder.build().ok().map(Arc::new)
+}
+
+/// the innermost `.paraignore` with an opinion on `path` decides
+fn is_ignored(path: &Path, is_dir: bool, ignores: &[Arc<Gitignore>]) -> bool {
+    for ignore in ignores.iter().rev() {
+        match ignore.matched(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => (),
+        }
+    }
+    false
+}
+
+fn walk(
+    path: &Path,
+    module: Option<PathBuf>,
+    roots: &[PathBuf],
+    para_dir: &Path,
+    ignores: &[Arc<Gitignore>],
+) -> Vec<Entry> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return vec![];
+    };
+    let entry = Entry {
+        path: path.to_path_buf(),
+        module: module.clone(),
+        is_dir: metadata.is_dir(),
+        is_symlink: metadata.file_type().is_symlink(),
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+    };
+    if !entry.is_dir {
+        return vec![entry];
+    }
+
+    let children: Vec<PathBuf> = match path.read_dir() {
+        Ok(dir) => dir.flatten().map(|e| e.path()).collect(),
+        Err(_) => vec![],
+    };
+    let is_root = roots.iter().any(|r| r == path);
+    let mut ignores = ignores.to_vec();
+    if let Some(ignore) = read_ignore_file(path) {
+        ignores.push(ignore);
+    }
+    let mut entries = vec![entry];
+    entries.par_extend(children.par_iter().flat_map_iter(|child| {
+        if child == para_dir || is_ignored(child, child.is_dir(), &ignores) {
+            return vec![];
+        }
+        // directories directly beneath a root are modules
+        let child_module = match &module {
+            Some(m) => Some(m.clone()),
+            None if is_root && child.is_dir() => Some(child.clone()),
+            None => None,
+        };
+        walk(child, child_module, roots, para_dir, &ignores)
+    }));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gitignore(dir: &str, lines: &[&str]) -> Arc<Gitignore> {
+        let mut builder = GitignoreBuilder::new(dir);
+        for line in lines {
+            builder.add_line(None, line).unwrap();
+        }
+        Arc::new(builder.build().unwrap())
+    }
+
+    #[test]
+    fn innermost_ignore_file_decides() {
+        let ignores = [
+            gitignore("/home", &["*.log", "build/"]),
+            gitignore("/home/module", &["!keep.log", "*.tmp"]),
+        ];
+        let ignored = |path: &str, is_dir| is_ignored(Path::new(path), is_dir, &ignores);
+        assert!(!ignored("/home/module/keep.log", false));
+        assert!(ignored("/home/module/other.log", false));
+        assert!(ignored("/home/module/scratch.tmp", false));
+        assert!(ignored("/home/module/build", true));
+        assert!(!ignored("/home/module/build", false));
+        assert!(!ignored("/home/module/notes.md", false));
+        // outside the module only the parent's file applies
+        assert!(is_ignored(Path::new("/home/keep.log"), false, &ignores[..1]));
+    }
+
+    #[test]
+    fn walk_applies_nested_ignore_files() {
+        let home = std::env::temp_dir().join(format!("para-walk-ignore-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let module = home.join("projects").join("module");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(home.join(IGNORE_FILE), "*.log\n").unwrap();
+        fs::write(module.join(IGNORE_FILE), "!keep.log\n").unwrap();
+        for file in ["keep.log", "other.log", "README.md"] {
+            fs::write(module.join(file), "").unwrap();
+        }
+        fs::create_dir_all(home.join(".para")).unwrap();
+        fs::write(home.join(".para").join("config.yaml"), "").unwrap();
+
+        let roots = [home.join("projects")];
+        let entries = walk(&home, None, &roots, &home.join(".para"), &[]);
+        let names: Vec<&str> = entries.iter()
+            .filter(|e| e.module.as_deref() == Some(module.as_path()))
+            .map(|e| e.file_name())
+            .collect();
+        assert!(names.contains(&"keep.log"));
+        assert!(names.contains(&"README.md"));
+        assert!(!names.contains(&"other.log"));
+        assert!(!entries.iter().any(|e| e.path.starts_with(home.join(".para"))));
+        fs::remove_dir_all(home).unwrap();
+    }
+}